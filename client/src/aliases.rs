@@ -0,0 +1,337 @@
+//! Player-defined command aliases.
+//!
+//! An alias names a sequence of server actions whose parameters may
+//! contain placeholders: `$1`, `$2`, ... for positional arguments and
+//! `$target`-style names for named ones (`$$` is a literal dollar sign).
+//! A value that is nothing but one placeholder takes the argument's JSON
+//! type when it parses as JSON, so `"$1"` given `40` sends the number 40.
+//! Aliases are saved per character at `<token_path>/aliases/<username>.json`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::store;
+
+/// Errors from defining or expanding aliases.
+#[derive(Debug, thiserror::Error)]
+pub enum AliasError {
+    /// Aliases belong to a character, so one must be logged in.
+    #[error("not logged in; call `connect` first")]
+    NoCharacter,
+    /// The alias has no name or no steps.
+    #[error("an alias needs a name and at least one step")]
+    Empty,
+    /// No alias with this name exists.
+    #[error("no alias named {0:?}")]
+    Unknown(String),
+    /// Placeholders that no argument was given for.
+    #[error("missing arguments for {}", .0.join(", "))]
+    MissingArguments(Vec<String>),
+}
+
+/// One server action of an alias.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AliasStep {
+    /// Server action to send (e.g. "say", "channel").
+    pub action: String,
+    /// Action parameters; string values may contain `$1` or `$name`
+    /// placeholders.
+    #[serde(default)]
+    pub params: Map<String, Value>,
+}
+
+/// A named sequence of actions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alias {
+    /// Name used to run the alias.
+    pub name: String,
+    /// What the alias is for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Actions to send, in order.
+    pub steps: Vec<AliasStep>,
+}
+
+impl Alias {
+    /// Substitutes arguments into the steps' parameters.
+    ///
+    /// `$1` is the first positional argument; any other `$name` is looked
+    /// up in `named`. A value that is exactly one placeholder keeps the
+    /// argument's JSON type if it parses as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AliasError::MissingArguments` listing every placeholder
+    /// that has no value.
+    pub fn expand(
+        &self,
+        positional: &[String],
+        named: &BTreeMap<String, String>,
+    ) -> Result<Vec<AliasStep>, AliasError> {
+        let mut missing = BTreeSet::new();
+        let mut resolve = |key: &str| {
+            let value = match key.parse::<usize>() {
+                Ok(index) => index.checked_sub(1).and_then(|i| positional.get(i)),
+                Err(_) => named.get(key),
+            };
+            if value.is_none() {
+                missing.insert(format!("${key}"));
+            }
+            value.cloned().unwrap_or_default()
+        };
+
+        let steps = self
+            .steps
+            .iter()
+            .map(|step| AliasStep {
+                action: step.action.clone(),
+                params: step
+                    .params
+                    .iter()
+                    .map(|(key, value)| (key.clone(), substitute_value(value, &mut resolve)))
+                    .collect(),
+            })
+            .collect();
+
+        if missing.is_empty() {
+            Ok(steps)
+        } else {
+            Err(AliasError::MissingArguments(missing.into_iter().collect()))
+        }
+    }
+}
+
+/// Substitutes placeholders in every string inside `value`.
+fn substitute_value(value: &Value, resolve: &mut impl FnMut(&str) -> String) -> Value {
+    match value {
+        Value::String(text) => match whole_placeholder(text) {
+            Some(key) => {
+                let arg = resolve(key);
+                serde_json::from_str(&arg).unwrap_or(Value::String(arg))
+            }
+            None => Value::String(substitute(text, resolve)),
+        },
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute_value(item, resolve))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| (key.clone(), substitute_value(item, resolve)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Returns the placeholder's key if `text` is a single placeholder and
+/// nothing else.
+fn whole_placeholder(text: &str) -> Option<&str> {
+    let key = text.strip_prefix('$')?;
+    let whole = if key.starts_with(|c: char| c.is_ascii_digit()) {
+        key.chars().all(|c| c.is_ascii_digit())
+    } else {
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    whole.then_some(key)
+}
+
+/// Replaces `$1` and `$name` placeholders in `text`.
+fn substitute(text: &str, resolve: &mut impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('$') {
+        out.push_str(&rest[..at]);
+        let after = &rest[at + 1..];
+        if let Some(tail) = after.strip_prefix('$') {
+            out.push('$');
+            rest = tail;
+            continue;
+        }
+        let len = if after.starts_with(|c: char| c.is_ascii_digit()) {
+            after.find(|c: char| !c.is_ascii_digit())
+        } else {
+            after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        }
+        .unwrap_or(after.len());
+        if len == 0 {
+            // A lone `$` is just text.
+            out.push('$');
+        } else {
+            out.push_str(&resolve(&after[..len]));
+        }
+        rest = &after[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[derive(Debug, Default)]
+struct AliasesInner {
+    file: Option<PathBuf>,
+    aliases: BTreeMap<String, Alias>,
+}
+
+/// The active character's aliases.
+#[derive(Debug, Clone, Default)]
+pub struct Aliases {
+    inner: Arc<Mutex<AliasesInner>>,
+}
+
+impl Aliases {
+    /// Switches to the aliases stored at `file`, loading them if it exists.
+    pub fn open(&self, file: PathBuf) {
+        let aliases = store::load(&file);
+        *self.lock() = AliasesInner {
+            file: Some(file),
+            aliases,
+        };
+    }
+
+    /// Forgets the active aliases, e.g. on disconnect.
+    pub fn close(&self) {
+        *self.lock() = AliasesInner::default();
+    }
+
+    /// Defines or replaces an alias and saves it, returning true if it
+    /// replaced an existing one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AliasError::NoCharacter` if no character's aliases are
+    /// open, or `AliasError::Empty` for a nameless or empty alias.
+    pub fn define(&self, alias: Alias) -> Result<bool, AliasError> {
+        if alias.name.trim().is_empty() || alias.steps.is_empty() {
+            return Err(AliasError::Empty);
+        }
+        let mut inner = self.lock();
+        if inner.file.is_none() {
+            return Err(AliasError::NoCharacter);
+        }
+        let replaced = inner.aliases.insert(alias.name.clone(), alias).is_some();
+        inner.save();
+        Ok(replaced)
+    }
+
+    /// Looks up an alias by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AliasError::Unknown` if there is no such alias.
+    pub fn get(&self, name: &str) -> Result<Alias, AliasError> {
+        self.lock()
+            .aliases
+            .get(name)
+            .cloned()
+            .ok_or_else(|| AliasError::Unknown(name.to_owned()))
+    }
+
+    /// Lists all aliases by name.
+    pub fn list(&self) -> Vec<Alias> {
+        self.lock().aliases.values().cloned().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, AliasesInner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl AliasesInner {
+    /// Writes the aliases to disk, if a character is selected.
+    fn save(&self) {
+        if let Some(file) = &self.file {
+            store::save(file, &self.aliases);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn alias(steps: Value) -> Alias {
+        Alias {
+            name: "nuke".to_owned(),
+            description: None,
+            steps: serde_json::from_value(steps).expect("valid steps"),
+        }
+    }
+
+    #[test]
+    fn placeholders_are_substituted() {
+        let nuke = alias(json!([
+            { "action": "use_ability", "params": { "ability": "fireball", "target": "$target" } },
+            { "action": "channel", "params": { "name": "party", "message": "Burned $1 for $$$2!" } }
+        ]));
+        let named = BTreeMap::from([("target".to_owned(), "goblin".to_owned())]);
+        let steps = nuke
+            .expand(&["Grik".to_owned(), "40".to_owned()], &named)
+            .expect("expands");
+
+        assert_eq!(steps[0].params["target"], "goblin");
+        assert_eq!(steps[1].params["message"], "Burned Grik for $40!");
+    }
+
+    #[test]
+    fn whole_placeholders_keep_json_types() {
+        let deposit = alias(json!([
+            { "action": "guild_deposit", "params": {
+                "amount": "$1", "item": "$2", "note": "$1 gold", "flags": ["$urgent"]
+            } }
+        ]));
+        let named = BTreeMap::from([("urgent".to_owned(), "true".to_owned())]);
+        let steps = deposit
+            .expand(&["40".to_owned(), "iron sword".to_owned()], &named)
+            .expect("expands");
+
+        assert_eq!(steps[0].params["amount"], json!(40));
+        assert_eq!(steps[0].params["item"], "iron sword");
+        assert_eq!(steps[0].params["note"], "40 gold");
+        assert_eq!(steps[0].params["flags"], json!([true]));
+    }
+
+    #[test]
+    fn missing_arguments_are_all_reported() {
+        let nuke = alias(json!([
+            { "action": "use_ability", "params": { "target": "$target", "note": "$1 costs $" } }
+        ]));
+        let err = nuke.expand(&[], &BTreeMap::new()).expect_err("missing");
+        assert!(matches!(
+            err,
+            AliasError::MissingArguments(missing) if missing == ["$1", "$target"]
+        ));
+    }
+
+    #[test]
+    fn aliases_are_saved_per_character() {
+        let dir = std::env::temp_dir().join(format!("wyvern-aliases-{}", std::process::id()));
+        let file = dir.join("aliases").join("aria.json");
+
+        let aliases = Aliases::default();
+        let nuke = alias(json!([{ "action": "look" }]));
+        assert!(matches!(
+            aliases.define(nuke.clone()),
+            Err(AliasError::NoCharacter)
+        ));
+
+        aliases.open(file.clone());
+        assert!(!aliases.define(nuke.clone()).expect("defined"));
+        assert!(aliases.define(nuke.clone()).expect("redefined"));
+
+        let reloaded = Aliases::default();
+        reloaded.open(file);
+        assert_eq!(reloaded.get("nuke").expect("found"), nuke);
+        assert!(matches!(reloaded.get("heal"), Err(AliasError::Unknown(_))));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
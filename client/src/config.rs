@@ -0,0 +1,336 @@
+//! Layered configuration: built-in defaults, the config file, the
+//! selected profile, then command-line flags.
+//!
+//! The file lives at `~/.weights-and-wyverns/config.toml`. Top-level
+//! keys apply to every profile; a `[profiles.<name>]` table overrides
+//! them when that profile is selected with `--profile` or
+//! `WYVERN_PROFILE`, or named by the top-level `profile` key:
+//!
+//! ```toml
+//! profile = "dev"
+//! push_events = true
+//!
+//! [profiles.dev]
+//! server = "ws://localhost:8080/ws"
+//! username = "aria"
+//! allow_raw = true
+//!
+//! [profiles.prod]
+//! server = "wss://wyverns.ai/ws"
+//! response_timeout_secs = 45
+//! record = true
+//! ```
+//!
+//! Event filters (see [`crate::filter`]) start from `[event_filter]`,
+//! replaced for one character by an `[event_filters.<name>]` table:
+//!
+//! ```toml
+//! [event_filter]
+//! verbosity = "normal"
+//! hide_channels = ["trade"]
+//!
+//! [event_filters.brom]
+//! verbosity = "minimal"
+//! ```
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::filter::EventFilter;
+use crate::notify::DEFAULT_URGENT_EVENTS;
+
+/// Default location of the config file.
+pub const DEFAULT_CONFIG_PATH: &str = "~/.weights-and-wyverns/config.toml";
+
+const DEFAULT_SERVER: &str = "ws://localhost:8080/ws";
+const DEFAULT_TOKEN_PATH: &str = "~/.weights-and-wyverns";
+
+/// Errors from loading the configuration.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    /// The config file exists but could not be read.
+    #[error("failed to read {path}: {source}")]
+    Read {
+        /// Path of the config file.
+        path: PathBuf,
+        /// Underlying I/O error.
+        source: std::io::Error,
+    },
+    /// The config file is not valid TOML for this schema.
+    #[error("invalid config file {path}: {source}")]
+    Parse {
+        /// Path of the config file.
+        path: PathBuf,
+        /// Underlying parse error.
+        source: toml::de::Error,
+    },
+    /// The selected profile is not defined in the file.
+    #[error("unknown profile {name:?}; defined profiles: {}", .defined.join(", "))]
+    UnknownProfile {
+        /// Profile that was asked for.
+        name: String,
+        /// Profiles the file defines.
+        defined: Vec<String>,
+    },
+}
+
+/// One layer of settings; unset values fall through to the layer below.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Layer {
+    /// WebSocket URL of the game server.
+    pub server: Option<String>,
+    /// Base directory for tokens and per-character data.
+    pub token_path: Option<String>,
+    /// Character `connect` logs in as when no username is given.
+    pub username: Option<String>,
+    /// Seconds to wait for the response to a command.
+    pub response_timeout_secs: Option<u64>,
+    /// Expose the `raw_command` tool.
+    pub allow_raw: Option<bool>,
+    /// Push urgent events to the MCP client as they arrive.
+    pub push_events: Option<bool>,
+    /// Event types treated as urgent.
+    pub urgent_events: Option<Vec<String>>,
+    /// Record session transcripts.
+    pub record: Option<bool>,
+    /// Loopback address to mirror the session to observers on.
+    pub observe: Option<SocketAddr>,
+    /// Event filter for every character without one of its own.
+    pub event_filter: Option<EventFilter>,
+    /// Event filters for particular characters.
+    pub event_filters: Option<BTreeMap<String, EventFilter>>,
+}
+
+impl Layer {
+    /// Returns this layer with every value set in `over` replaced.
+    #[must_use]
+    pub fn merge(self, over: Self) -> Self {
+        Self {
+            server: over.server.or(self.server),
+            token_path: over.token_path.or(self.token_path),
+            username: over.username.or(self.username),
+            response_timeout_secs: over.response_timeout_secs.or(self.response_timeout_secs),
+            allow_raw: over.allow_raw.or(self.allow_raw),
+            push_events: over.push_events.or(self.push_events),
+            urgent_events: over.urgent_events.or(self.urgent_events),
+            record: over.record.or(self.record),
+            observe: over.observe.or(self.observe),
+            event_filter: over.event_filter.or(self.event_filter),
+            event_filters: match (self.event_filters, over.event_filters) {
+                (Some(mut filters), Some(over)) => {
+                    filters.extend(over);
+                    Some(filters)
+                }
+                (filters, over) => over.or(filters),
+            },
+        }
+    }
+
+    /// Fills in built-in defaults for anything still unset.
+    pub fn finish(self) -> Settings {
+        Settings {
+            server: self.server.unwrap_or_else(|| DEFAULT_SERVER.to_owned()),
+            token_path: self
+                .token_path
+                .unwrap_or_else(|| DEFAULT_TOKEN_PATH.to_owned()),
+            username: self.username,
+            response_timeout: self.response_timeout_secs.map(Duration::from_secs),
+            allow_raw: self.allow_raw.unwrap_or(false),
+            push_events: self.push_events.unwrap_or(false),
+            urgent_events: self.urgent_events.unwrap_or_else(|| {
+                DEFAULT_URGENT_EVENTS
+                    .iter()
+                    .map(|&kind| kind.to_owned())
+                    .collect()
+            }),
+            record: self.record.unwrap_or(false),
+            observe: self.observe,
+            event_filter: self.event_filter.unwrap_or_default(),
+            event_filters: self.event_filters.unwrap_or_default(),
+        }
+    }
+}
+
+/// Fully resolved settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// WebSocket URL of the game server.
+    pub server: String,
+    /// Base directory for tokens and per-character data.
+    pub token_path: String,
+    /// Character `connect` logs in as when no username is given.
+    pub username: Option<String>,
+    /// Response timeout, if not the connection's default.
+    pub response_timeout: Option<Duration>,
+    /// Expose the `raw_command` tool.
+    pub allow_raw: bool,
+    /// Push urgent events to the MCP client as they arrive.
+    pub push_events: bool,
+    /// Event types treated as urgent.
+    pub urgent_events: Vec<String>,
+    /// Record session transcripts.
+    pub record: bool,
+    /// Loopback address to mirror the session to observers on.
+    pub observe: Option<SocketAddr>,
+    /// Event filter for every character without one of its own.
+    pub event_filter: EventFilter,
+    /// Event filters for particular characters.
+    pub event_filters: BTreeMap<String, EventFilter>,
+}
+
+/// Contents of the config file.
+#[derive(Debug, Default)]
+pub struct ConfigFile {
+    /// Profile used when none is selected.
+    profile: Option<String>,
+    /// Named overrides of the top-level settings.
+    profiles: BTreeMap<String, Layer>,
+    /// Settings shared by every profile.
+    base: Layer,
+}
+
+impl ConfigFile {
+    /// Reads the config file at `path`.
+    ///
+    /// A missing file is an empty configuration unless `required` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ConfigError`] if the file cannot be read or parsed.
+    pub fn load(path: &Path, required: bool) -> Result<Self, ConfigError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && !required => {
+                return Ok(Self::default());
+            }
+            Err(source) => {
+                return Err(ConfigError::Read {
+                    path: path.to_owned(),
+                    source,
+                })
+            }
+        };
+        Self::parse(&contents).map_err(|source| ConfigError::Parse {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    /// Parses the contents of a config file.
+    ///
+    /// Split by hand rather than with `#[serde(flatten)]`, which would
+    /// stop unknown top-level keys from being rejected.
+    fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        let mut table: toml::Table = toml::from_str(contents)?;
+        let profile = table
+            .remove("profile")
+            .map(toml::Value::try_into)
+            .transpose()?;
+        let profiles = table
+            .remove("profiles")
+            .map(toml::Value::try_into)
+            .transpose()?
+            .unwrap_or_default();
+        let base = toml::Value::Table(table).try_into()?;
+        Ok(Self {
+            profile,
+            profiles,
+            base,
+        })
+    }
+
+    /// Returns the file's settings with `profile` (or the file's default
+    /// profile) applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::UnknownProfile` if the profile is not defined.
+    pub fn resolve(mut self, profile: Option<&str>) -> Result<Layer, ConfigError> {
+        let Some(name) = profile.map(str::to_owned).or(self.profile) else {
+            return Ok(self.base);
+        };
+        match self.profiles.remove(&name) {
+            Some(layer) => Ok(self.base.merge(layer)),
+            None => Err(ConfigError::UnknownProfile {
+                name,
+                defined: self.profiles.into_keys().collect(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Verbosity;
+
+    const CONFIG: &str = r#"
+        profile = "dev"
+        push_events = true
+        urgent_events = ["tell"]
+
+        [event_filters.aria]
+        verbosity = "minimal"
+
+        [profiles.dev]
+        server = "ws://localhost:8080/ws"
+        username = "aria"
+
+        [profiles.dev.event_filter]
+        hide_channels = ["trade"]
+
+        [profiles.prod]
+        server = "wss://wyverns.ai/ws"
+        response_timeout_secs = 45
+        push_events = false
+    "#;
+
+    #[test]
+    fn profiles_override_shared_settings() {
+        let file = || ConfigFile::parse(CONFIG).expect("valid config");
+
+        let dev = file().resolve(None).expect("default profile").finish();
+        assert_eq!(dev.server, "ws://localhost:8080/ws");
+        assert_eq!(dev.username.as_deref(), Some("aria"));
+        assert!(dev.push_events);
+        assert_eq!(dev.urgent_events, ["tell"]);
+        assert_eq!(dev.event_filter.hide_channels, ["trade"]);
+        assert_eq!(dev.event_filters["aria"].verbosity, Verbosity::Minimal);
+
+        let prod = file().resolve(Some("prod")).expect("prod profile").finish();
+        assert_eq!(prod.server, "wss://wyverns.ai/ws");
+        assert_eq!(prod.username, None);
+        assert_eq!(prod.response_timeout, Some(Duration::from_secs(45)));
+        assert!(!prod.push_events);
+        assert_eq!(prod.token_path, DEFAULT_TOKEN_PATH);
+        assert_eq!(prod.event_filter, EventFilter::default());
+
+        assert!(matches!(
+            file().resolve(Some("staging")),
+            Err(ConfigError::UnknownProfile { defined, .. }) if defined == ["dev", "prod"]
+        ));
+    }
+
+    #[test]
+    fn flags_override_the_file() {
+        let file = ConfigFile::parse(CONFIG).expect("valid config");
+        let flags = Layer {
+            server: Some("ws://127.0.0.1:9000/ws".to_owned()),
+            push_events: Some(false),
+            ..Layer::default()
+        };
+        let settings = file.resolve(None).expect("profile").merge(flags).finish();
+        assert_eq!(settings.server, "ws://127.0.0.1:9000/ws");
+        assert_eq!(settings.username.as_deref(), Some("aria"));
+        assert!(!settings.push_events);
+
+        assert!(ConfigFile::parse("sever = \"typo\"").is_err());
+        let missing = ConfigFile::load(Path::new("/nonexistent/config.toml"), false);
+        assert!(missing.expect("optional").resolve(None).expect("base") == Layer::default());
+    }
+}
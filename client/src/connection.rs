@@ -2,21 +2,68 @@
 //!
 //! Manages a persistent WebSocket link to the centralized game server,
 //! handling request/response correlation and push event forwarding.
+//! If the socket drops, a supervisor task reconnects with exponential
+//! backoff and resumes the session using the stored credentials.
+//! Keepalive pings detect dead peers and feed latency tracking.
+//!
+//! The link moves through a small lifecycle:
+//!
+//! ```text
+//! Closed -> Connecting -> Connected -> Reconnecting -> Resuming
+//!                            ^  |           |              |
+//!                            |  +-> Closed <+              |
+//!                            +-----------------------------+
+//! ```
+//!
+//! A socket that drops while resuming goes back to reconnecting, and a
+//! session the server refuses to resume closes the link.
+//! Commands are refused in both states, so nothing reaches a new socket
+//! before the session has re-authenticated on it.
 
 use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex as StdMutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 
+use futures_util::stream::SplitStream;
 use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
 use serde_json::Value;
-use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Mutex};
+use tokio::task::JoinHandle;
 use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::heartbeat::{Heartbeat, LatencyStats, HEARTBEAT_INTERVAL};
+use crate::protocol::{Notice, PushEvent, Request, Response, ResponseBody};
+use crate::recorder::{Direction, SessionRecorder};
 
-/// Timeout for waiting on a server response to a command.
+/// Default timeout for waiting on a server response to a command.
 ///
 /// Based on upstream server timeout policies; large enough
 /// for the game server to process any command.
-const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Delay before the first reconnect attempt after the socket drops.
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// Upper bound on the delay between reconnect attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Number of reconnect attempts before giving up on the session.
+///
+/// With the backoff above this covers roughly two and a half minutes
+/// of outage, enough to ride out a flaky Wi-Fi hand-off.
+const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+
+/// How often the reader checks whether the outstanding ping is overdue.
+const HEARTBEAT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Push events held for live subscribers before the slowest one skips ahead.
+const EVENT_FEED_CAPACITY: usize = 256;
+
+/// Read half of a game server WebSocket.
+type WsReader = SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>;
 
 /// Errors arising from game server communication.
 #[derive(Debug, thiserror::Error)]
@@ -44,211 +91,721 @@ pub enum ConnectionError {
     #[error("not connected to game server — call `connect` first")]
     NotConnected,
 
+    /// The connection to the game server was lost.
+    ///
+    /// Returned for requests in flight when the socket closed and for
+    /// commands sent while the supervisor is reconnecting.
+    #[error("connection to game server lost")]
+    Disconnected,
+
     /// The response channel was dropped before a response arrived.
     #[error("response channel closed unexpectedly")]
     ChannelClosed,
 }
 
+/// Lifecycle of the link to the game server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    /// Performing the initial WebSocket handshake.
+    Connecting,
+    /// The socket is open and commands can be sent.
+    Connected,
+    /// The socket dropped and the supervisor is trying to re-establish it.
+    Reconnecting,
+    /// A new socket is open and the session is re-authenticating on it.
+    Resuming,
+    /// No connection, either never opened, closed on request, or given up.
+    Closed,
+}
+
 /// A pending request awaiting its response from the server.
-type PendingRequest = oneshot::Sender<Value>;
+type PendingRequest = oneshot::Sender<Result<Response, ConnectionError>>;
 
-/// Shared state for the background WebSocket reader task.
+/// Username and token used to re-authenticate after a reconnect.
+#[derive(Debug, Clone)]
+struct Credentials {
+    username: String,
+    token: String,
+}
+
+/// Destinations for incoming push events.
+///
+/// Every event goes to the buffer drained by tool responses and is
+/// copied to the live feed for anything that reacts as events arrive.
+#[derive(Debug, Clone)]
+struct EventSink {
+    buffer: mpsc::UnboundedSender<PushEvent>,
+    feed: broadcast::Sender<PushEvent>,
+}
+
+impl EventSink {
+    fn emit(&self, event: PushEvent) {
+        // No live subscribers is the common case, not an error.
+        let _ = self.feed.send(event.clone());
+        let _ = self.buffer.send(event);
+    }
+}
+
+/// Shared state for the background WebSocket tasks.
 #[derive(Debug)]
 struct ConnectionInner {
     pending: HashMap<String, PendingRequest>,
     next_id: u64,
+    write_tx: Option<mpsc::Sender<Message>>,
+    credentials: Option<Credentials>,
+    recorder: Option<SessionRecorder>,
+    response_timeout: Duration,
+}
+
+/// Settings for a [`GameConnection`].
+#[derive(Debug, Clone)]
+pub struct ConnectionOptions {
+    /// How long to wait for the response to a command.
+    pub response_timeout: Duration,
+    /// Where to write the session's traffic, if anywhere.
+    pub recorder: Option<SessionRecorder>,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            response_timeout: DEFAULT_RESPONSE_TIMEOUT,
+            recorder: None,
+        }
+    }
 }
 
 /// Manages a WebSocket connection to the game server.
 ///
-/// Spawns a background task that reads from the WebSocket, routing
-/// responses to waiting oneshots and push events to the event channel.
+/// Spawns a supervisor task that reads from the WebSocket, routing
+/// responses to waiting oneshots and push events to the event channel,
+/// and transparently reconnects when the socket drops.
 #[derive(Debug)]
 pub struct GameConnection {
     inner: Arc<Mutex<ConnectionInner>>,
-    write_tx: Option<mpsc::Sender<Message>>,
-    event_tx: mpsc::UnboundedSender<Value>,
+    state: Arc<watch::Sender<ConnectionState>>,
+    heartbeat: Arc<StdMutex<Heartbeat>>,
+    events: EventSink,
     shutdown_tx: Option<oneshot::Sender<()>>,
+    supervisor: Option<JoinHandle<()>>,
 }
 
 impl GameConnection {
     /// Creates a new unconnected game connection.
     ///
-    /// Push events will be forwarded to `event_tx` for buffering.
-    pub fn new(event_tx: mpsc::UnboundedSender<Value>) -> Self {
+    /// Push events will be forwarded to `event_tx` for buffering, and
+    /// copied to any live subscribers (see [`GameConnection::subscribe`]).
+    pub fn new(event_tx: mpsc::UnboundedSender<PushEvent>, options: ConnectionOptions) -> Self {
         Self {
             inner: Arc::new(Mutex::new(ConnectionInner {
                 pending: HashMap::new(),
                 next_id: 1,
+                write_tx: None,
+                credentials: None,
+                recorder: options.recorder,
+                response_timeout: options.response_timeout,
             })),
-            write_tx: None,
-            event_tx,
+            state: Arc::new(watch::Sender::new(ConnectionState::Closed)),
+            heartbeat: Arc::new(StdMutex::new(Heartbeat::default())),
+            events: EventSink {
+                buffer: event_tx,
+                feed: broadcast::Sender::new(EVENT_FEED_CAPACITY),
+            },
             shutdown_tx: None,
+            supervisor: None,
         }
     }
 
     /// Returns true if a WebSocket connection is active.
+    ///
+    /// Becomes false as soon as the socket drops, even while the
+    /// supervisor is still trying to reconnect.
     pub fn is_connected(&self) -> bool {
-        self.write_tx.is_some()
+        *self.state.borrow() == ConnectionState::Connected
+    }
+
+    /// Returns a handle for observing link health without locking the connection.
+    pub fn monitor(&self) -> LinkMonitor {
+        LinkMonitor {
+            state: self.state.subscribe(),
+            heartbeat: Arc::clone(&self.heartbeat),
+        }
+    }
+
+    /// Returns a handle for sending commands from background tasks.
+    pub fn handle(&self) -> ConnectionHandle {
+        ConnectionHandle {
+            inner: Arc::clone(&self.inner),
+            state: Arc::clone(&self.state),
+            events: self.events.clone(),
+        }
+    }
+
+    /// Subscribes to push events as they arrive.
+    ///
+    /// Unlike the buffered channel, the live feed is lossy: a subscriber
+    /// that falls more than a few hundred events behind skips ahead.
+    pub fn subscribe(&self) -> broadcast::Receiver<PushEvent> {
+        self.events.feed.subscribe()
     }
 
     /// Establishes a WebSocket connection to the game server.
     ///
-    /// Spawns a background reader task that routes incoming messages.
+    /// Spawns a background supervisor that routes incoming messages and
+    /// reconnects with exponential backoff if the socket drops. Any
+    /// previous session on this connection is shut down first.
     ///
     /// # Errors
     ///
     /// Returns `ConnectionError::Connect` if the WebSocket handshake fails.
     pub async fn connect(&mut self, url: impl AsRef<str>) -> Result<(), ConnectionError> {
-        let url = url.as_ref();
-        let request = tokio_tungstenite::tungstenite::http::Request::builder()
-            .uri(url)
-            .header(
-                "User-Agent",
-                concat!("wyvern-client/", env!("CARGO_PKG_VERSION")),
-            )
-            .header("Connection", "Upgrade")
-            .header("Upgrade", "websocket")
-            .header("Sec-WebSocket-Version", "13")
-            .header(
-                "Sec-WebSocket-Key",
-                tokio_tungstenite::tungstenite::handshake::client::generate_key(),
-            )
-            .header("Host", url_host(url))
-            .body(())
-            .expect("valid WebSocket request");
-        let (ws_stream, _response) =
-            tokio_tungstenite::connect_async(request)
-                .await
-                .map_err(|e| ConnectionError::Connect {
-                    url: url.to_owned(),
-                    source: e,
-                })?;
-
-        let (ws_write, ws_read) = ws_stream.split();
-
-        // Channel for sending messages to the WebSocket writer task.
-        let (write_tx, mut write_rx) = mpsc::channel::<Message>(64);
-
-        // Shutdown signal for the background tasks.
-        let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
+        self.stop_supervisor().await;
 
-        // Writer task: forwards messages from the channel to the WebSocket.
-        tokio::spawn(async move {
-            let mut ws_write = ws_write;
-            while let Some(msg) = write_rx.recv().await {
-                if ws_write.send(msg).await.is_err() {
-                    break;
-                }
-            }
-        });
-
-        // Reader task: routes incoming messages to pending requests or events.
-        let inner = Arc::clone(&self.inner);
-        let event_tx = self.event_tx.clone();
-        tokio::spawn(async move {
-            let mut ws_read = ws_read;
-            loop {
-                tokio::select! {
-                    msg = ws_read.next() => {
-                        match msg {
-                            Some(Ok(Message::Text(text))) => {
-                                route_message(&inner, &event_tx, &text).await;
-                            }
-                            Some(Ok(Message::Close(_)) | Err(_)) | None => break,
-                            _ => {}
-                        }
-                    }
-                    _ = &mut shutdown_rx => break,
-                }
+        let url = url.as_ref();
+        set_state(&self.state, ConnectionState::Connecting);
+        let (write_tx, ws_read) = match open_socket(url, &self.heartbeat).await {
+            Ok(link) => link,
+            Err(err) => {
+                set_state(&self.state, ConnectionState::Closed);
+                return Err(err);
             }
-        });
+        };
+        self.inner.lock().await.write_tx = Some(write_tx);
+        set_state(&self.state, ConnectionState::Connected);
 
-        self.write_tx = Some(write_tx);
+        // Shutdown signal for the supervisor task.
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        self.supervisor = Some(tokio::spawn(supervise(
+            url.to_owned(),
+            Arc::clone(&self.inner),
+            Arc::clone(&self.state),
+            Arc::clone(&self.heartbeat),
+            self.events.clone(),
+            ws_read,
+            shutdown_rx,
+        )));
         self.shutdown_tx = Some(shutdown_tx);
 
         tracing::info!(server.url = url, "connection.established");
         Ok(())
     }
 
+    /// Remembers the credentials used to resume the session after a reconnect.
+    pub async fn set_credentials(&self, username: impl Into<String>, token: impl Into<String>) {
+        self.inner.lock().await.credentials = Some(Credentials {
+            username: username.into(),
+            token: token.into(),
+        });
+    }
+
+    /// Returns the username of the logged-in character, if any.
+    pub async fn username(&self) -> Option<String> {
+        let inner = self.inner.lock().await;
+        inner
+            .credentials
+            .as_ref()
+            .map(|credentials| credentials.username.clone())
+    }
+
+    /// Returns the token the logged-in character authenticated with, if any.
+    pub async fn token(&self) -> Option<String> {
+        let inner = self.inner.lock().await;
+        inner
+            .credentials
+            .as_ref()
+            .map(|credentials| credentials.token.clone())
+    }
+
     /// Sends a command to the game server and awaits the response.
     ///
     /// Assigns a unique message ID for request/response correlation.
-    /// Times out after 30 seconds.
+    /// Times out after the configured response timeout (30 seconds by default).
     ///
     /// # Errors
     ///
     /// Returns `ConnectionError::NotConnected` if no connection is active,
-    /// `ConnectionError::Timeout` if the server does not respond, or
-    /// `ConnectionError::Send` if the write fails.
+    /// `ConnectionError::Disconnected` if the socket drops while waiting or
+    /// is being re-established, `ConnectionError::Timeout` if the server
+    /// does not respond, or `ConnectionError::Send` if the write fails.
     pub async fn send_command(
         &self,
         action: impl AsRef<str>,
         params: Value,
-    ) -> Result<Value, ConnectionError> {
-        let write_tx = self
-            .write_tx
-            .as_ref()
-            .ok_or(ConnectionError::NotConnected)?;
+    ) -> Result<Response, ConnectionError> {
+        send_request(&self.inner, &self.state, action.as_ref(), params).await
+    }
 
-        let (msg_id, rx) = {
+    /// Gracefully closes the WebSocket connection.
+    pub async fn disconnect(&mut self) {
+        let write_tx = {
             let mut inner = self.inner.lock().await;
-            let id = format!("msg-{:04}", inner.next_id);
-            inner.next_id += 1;
-            let (tx, rx) = oneshot::channel();
-            inner.pending.insert(id.clone(), tx);
-            (id, rx)
+            inner.credentials = None;
+            inner.write_tx.clone()
         };
+        if let Some(write_tx) = write_tx {
+            let _ = write_tx.send(Message::Close(None)).await;
+        }
+        self.stop_supervisor().await;
+        tracing::info!("connection.closed");
+    }
 
-        let payload = serde_json::json!({
-            "id": msg_id,
-            "action": action.as_ref(),
-            "params": params,
-        });
+    /// Signals the supervisor to stop and waits for it to release the link.
+    ///
+    /// Waiting matters: a supervisor still winding down must not tear
+    /// down the socket that a subsequent `connect` installs.
+    async fn stop_supervisor(&mut self) {
+        if let Some(shutdown_tx) = self.shutdown_tx.take() {
+            let _ = shutdown_tx.send(());
+        }
+        if let Some(supervisor) = self.supervisor.take() {
+            let _ = supervisor.await;
+        }
+    }
+}
 
-        let msg = Message::Text(payload.to_string().into());
-        write_tx
-            .send(msg)
-            .await
-            .map_err(|_send_err| ConnectionError::NotConnected)?;
+/// Read-only view of link health, shareable without locking the connection.
+#[derive(Debug, Clone)]
+pub struct LinkMonitor {
+    state: watch::Receiver<ConnectionState>,
+    heartbeat: Arc<StdMutex<Heartbeat>>,
+}
+
+impl LinkMonitor {
+    /// Returns the current lifecycle state of the link.
+    pub fn state(&self) -> ConnectionState {
+        *self.state.borrow()
+    }
+
+    /// Returns the rolling round-trip latency measured by the heartbeat.
+    pub fn latency(&self) -> LatencyStats {
+        lock_heartbeat(&self.heartbeat).latency(Instant::now())
+    }
+}
+
+/// Cloneable handle for sending commands without locking the connection.
+///
+/// Background tasks use this to act on events while a tool call may be
+/// holding the connection. It follows reconnects and stays valid across
+/// `connect` calls on the same [`GameConnection`].
+#[derive(Debug, Clone)]
+pub struct ConnectionHandle {
+    inner: Arc<Mutex<ConnectionInner>>,
+    state: Arc<watch::Sender<ConnectionState>>,
+    events: EventSink,
+}
 
-        tracing::debug!(
-            msg.id = %msg_id,
-            msg.action = action.as_ref(),
-            "connection.command.sent"
+impl ConnectionHandle {
+    /// Sends a command to the game server and awaits the response.
+    ///
+    /// # Errors
+    ///
+    /// Fails exactly like [`GameConnection::send_command`].
+    pub async fn send_command(
+        &self,
+        action: impl AsRef<str>,
+        params: Value,
+    ) -> Result<Response, ConnectionError> {
+        send_request(&self.inner, &self.state, action.as_ref(), params).await
+    }
+
+    /// Delivers a client-generated event alongside the server's push events.
+    pub fn emit(&self, event: PushEvent) {
+        self.events.emit(event);
+    }
+}
+
+/// Moves the link to a new lifecycle state, logging the transition.
+fn set_state(state: &watch::Sender<ConnectionState>, next: ConnectionState) {
+    let previous = state.send_replace(next);
+    if previous != next {
+        tracing::debug!(from = ?previous, to = ?next, "connection.state.changed");
+    }
+}
+
+/// Locks the heartbeat state, recovering from a poisoned lock.
+///
+/// The state is plain bookkeeping, so a panic mid-update cannot
+/// leave it in a state worth refusing to read.
+fn lock_heartbeat(heartbeat: &StdMutex<Heartbeat>) -> MutexGuard<'_, Heartbeat> {
+    heartbeat.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Performs the WebSocket handshake and spawns the writer task.
+///
+/// Returns the channel feeding the writer and the read half of the socket.
+async fn open_socket(
+    url: &str,
+    heartbeat: &Arc<StdMutex<Heartbeat>>,
+) -> Result<(mpsc::Sender<Message>, WsReader), ConnectionError> {
+    let request = tokio_tungstenite::tungstenite::http::Request::builder()
+        .uri(url)
+        .header(
+            "User-Agent",
+            concat!("wyvern-client/", env!("CARGO_PKG_VERSION")),
+        )
+        .header("Connection", "Upgrade")
+        .header("Upgrade", "websocket")
+        .header("Sec-WebSocket-Version", "13")
+        .header(
+            "Sec-WebSocket-Key",
+            tokio_tungstenite::tungstenite::handshake::client::generate_key(),
+        )
+        .header("Host", url_host(url))
+        .body(())
+        .expect("valid WebSocket request");
+    let (ws_stream, _response) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| ConnectionError::Connect {
+            url: url.to_owned(),
+            source: e,
+        })?;
+
+    let (ws_write, ws_read) = ws_stream.split();
+
+    // Channel for sending messages to the WebSocket writer task.
+    let (write_tx, mut write_rx) = mpsc::channel::<Message>(64);
+
+    lock_heartbeat(heartbeat).reset();
+    let heartbeat = Arc::clone(heartbeat);
+
+    // Writer task: forwards messages from the channel to the WebSocket and
+    // sends keepalive pings. Exits once every sender has been dropped.
+    tokio::spawn(async move {
+        let mut ws_write = ws_write;
+        let mut ping_interval = tokio::time::interval_at(
+            tokio::time::Instant::now() + HEARTBEAT_INTERVAL,
+            HEARTBEAT_INTERVAL,
         );
+        loop {
+            let msg = tokio::select! {
+                msg = write_rx.recv() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                _ = ping_interval.tick() => {
+                    let payload = lock_heartbeat(&heartbeat).ping_sent(Instant::now());
+                    Message::Ping(payload.to_vec().into())
+                }
+            };
+            if ws_write.send(msg).await.is_err() {
+                break;
+            }
+        }
+    });
 
-        match tokio::time::timeout(RESPONSE_TIMEOUT, rx).await {
-            Ok(Ok(response)) => Ok(response),
-            Ok(Err(_)) => Err(ConnectionError::ChannelClosed),
-            Err(_) => {
-                // Remove the stale pending entry.
-                let mut inner = self.inner.lock().await;
-                inner.pending.remove(&msg_id);
-                Err(ConnectionError::Timeout(RESPONSE_TIMEOUT))
+    Ok((write_tx, ws_read))
+}
+
+/// Owns the connection for its whole lifetime.
+///
+/// Runs a reader task per socket, and when the socket drops fails all
+/// in-flight requests, reconnects with exponential backoff, and resumes
+/// the session with the stored credentials before reporting the link
+/// connected again. Exits on shutdown, once reconnect attempts are
+/// exhausted, or when the server refuses to resume the session.
+async fn supervise(
+    url: String,
+    inner: Arc<Mutex<ConnectionInner>>,
+    state: Arc<watch::Sender<ConnectionState>>,
+    heartbeat: Arc<StdMutex<Heartbeat>>,
+    events: EventSink,
+    mut ws_read: WsReader,
+    mut shutdown_rx: oneshot::Receiver<()>,
+) {
+    let mut resuming = false;
+    loop {
+        let mut reader = tokio::spawn(read_messages(
+            Arc::clone(&inner),
+            events.clone(),
+            Arc::clone(&heartbeat),
+            ws_read,
+        ));
+
+        // The reader must already be running to route the resume response.
+        let resume = resume_session(&inner, resuming);
+        tokio::pin!(resume);
+        let stop = loop {
+            tokio::select! {
+                _ = &mut reader => break false,
+                _ = &mut shutdown_rx => {
+                    reader.abort();
+                    break true;
+                }
+                resumed = &mut resume, if resuming => {
+                    resuming = false;
+                    if let Err(reason) = resumed {
+                        reader.abort();
+                        events.emit(PushEvent::ConnectionLost(Notice::new(format!(
+                            "Reconnected to the game server, but the session could not be \
+                             resumed: {reason}. Call `connect` to log in again."
+                        ))));
+                        break true;
+                    }
+                    set_state(&state, ConnectionState::Connected);
+                    tracing::info!(server.url = %url, "connection.reconnected");
+                    events.emit(PushEvent::ConnectionRestored(Notice::new(
+                        "Reconnected to the game server.",
+                    )));
+                }
             }
+        };
+        if stop {
+            break;
         }
+
+        set_state(&state, ConnectionState::Reconnecting);
+        let failed = drop_link(&inner).await;
+        tracing::warn!(server.url = %url, "connection.lost");
+        events.emit(PushEvent::ConnectionLost(Notice::new(format!(
+            "Connection to the game server was lost; {failed} pending command(s) failed. \
+             Reconnecting automatically."
+        ))));
+
+        let (write_tx, next_read) = match reconnect(&url, &heartbeat, &mut shutdown_rx).await {
+            Reconnect::Linked(write_tx, next_read) => (write_tx, next_read),
+            Reconnect::Shutdown => break,
+            Reconnect::GaveUp => {
+                events.emit(PushEvent::ConnectionLost(Notice::new(format!(
+                    "Could not reconnect to the game server after {MAX_RECONNECT_ATTEMPTS} \
+                     attempts, so the client stopped trying. Call `connect` to log in again."
+                ))));
+                break;
+            }
+        };
+        inner.lock().await.write_tx = Some(write_tx);
+        set_state(&state, ConnectionState::Resuming);
+        ws_read = next_read;
+        resuming = true;
     }
 
-    /// Gracefully closes the WebSocket connection.
-    pub async fn disconnect(&mut self) {
-        if let Some(write_tx) = self.write_tx.take() {
-            let _ = write_tx.send(Message::Close(None)).await;
+    set_state(&state, ConnectionState::Closed);
+    drop_link(&inner).await;
+}
+
+/// Reads from the WebSocket until it closes, errors, or stops answering pings.
+async fn read_messages(
+    inner: Arc<Mutex<ConnectionInner>>,
+    events: EventSink,
+    heartbeat: Arc<StdMutex<Heartbeat>>,
+    mut ws_read: WsReader,
+) {
+    let mut check_interval = tokio::time::interval(HEARTBEAT_CHECK_INTERVAL);
+    loop {
+        tokio::select! {
+            msg = ws_read.next() => match msg {
+                Some(Ok(Message::Text(text))) => route_message(&inner, &events, &text).await,
+                Some(Ok(Message::Pong(payload))) => {
+                    let rtt = lock_heartbeat(&heartbeat).pong_received(&payload, Instant::now());
+                    if let Some(rtt) = rtt {
+                        tracing::trace!(rtt.ms = rtt.as_millis(), "connection.heartbeat.pong");
+                    }
+                }
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = check_interval.tick() => {
+                if lock_heartbeat(&heartbeat).is_overdue(Instant::now()) {
+                    tracing::warn!("connection.heartbeat.timeout");
+                    break;
+                }
+            }
         }
-        if let Some(shutdown_tx) = self.shutdown_tx.take() {
-            let _ = shutdown_tx.send(());
+    }
+}
+
+/// Detaches the writer and fails every in-flight request.
+///
+/// Each waiting `send_command` resolves immediately with
+/// `ConnectionError::Disconnected` instead of running into the timeout.
+/// Returns the number of requests that were failed.
+async fn drop_link(inner: &Arc<Mutex<ConnectionInner>>) -> usize {
+    let mut inner = inner.lock().await;
+    inner.write_tx = None;
+    let failed = inner.pending.len();
+    for (_, tx) in inner.pending.drain() {
+        let _ = tx.send(Err(ConnectionError::Disconnected));
+    }
+    if failed > 0 {
+        tracing::warn!(requests.failed = failed, "connection.pending.failed");
+    }
+    failed
+}
+
+/// How an attempt to re-establish the socket ended.
+enum Reconnect {
+    /// A new socket is open: the channel feeding its writer and its read half.
+    Linked(mpsc::Sender<Message>, WsReader),
+    /// The connection was shut down while waiting.
+    Shutdown,
+    /// Every attempt failed.
+    GaveUp,
+}
+
+/// Attempts to re-establish the socket with exponential backoff.
+async fn reconnect(
+    url: &str,
+    heartbeat: &Arc<StdMutex<Heartbeat>>,
+    shutdown_rx: &mut oneshot::Receiver<()>,
+) -> Reconnect {
+    for attempt in 0..MAX_RECONNECT_ATTEMPTS {
+        let delay = reconnect_delay(attempt);
+        tokio::select! {
+            () = tokio::time::sleep(delay) => {}
+            _ = &mut *shutdown_rx => return Reconnect::Shutdown,
+        }
+
+        match open_socket(url, heartbeat).await {
+            Ok((write_tx, ws_read)) => return Reconnect::Linked(write_tx, ws_read),
+            Err(err) => tracing::warn!(
+                error = %err,
+                reconnect.attempt = attempt + 1,
+                "connection.reconnect.failed"
+            ),
         }
-        tracing::info!("connection.closed");
     }
+
+    tracing::error!(server.url = url, "connection.reconnect.exhausted");
+    Reconnect::GaveUp
 }
 
-/// Routes an incoming WebSocket text message to either a pending request
-/// or the event buffer.
-async fn route_message(
+/// Returns the backoff delay before the given zero-based reconnect attempt.
+fn reconnect_delay(attempt: u32) -> Duration {
+    INITIAL_RECONNECT_DELAY
+        .saturating_mul(2_u32.saturating_pow(attempt))
+        .min(MAX_RECONNECT_DELAY)
+}
+
+/// Re-sends the `connect` auth after a reconnect, if credentials are known.
+///
+/// Does nothing unless `resuming`. Sends on the new socket directly, as
+/// other commands are refused until this returns.
+///
+/// # Errors
+///
+/// Returns why the session could not be resumed: the server's error if
+/// it refused the login, or the transport error if the login failed.
+async fn resume_session(inner: &Arc<Mutex<ConnectionInner>>, resuming: bool) -> Result<(), String> {
+    if !resuming {
+        return Ok(());
+    }
+    let Some(credentials) = inner.lock().await.credentials.clone() else {
+        return Ok(());
+    };
+
+    let params = serde_json::json!({
+        "username": credentials.username,
+        "token": credentials.token,
+    });
+    match send_on_link(inner, "connect", params).await {
+        Ok(Response {
+            body: ResponseBody::Error { error },
+            ..
+        }) => {
+            tracing::warn!(
+                error.code = ?error.code,
+                error.message = %error.message,
+                username = %credentials.username,
+                "connection.session.resume_rejected"
+            );
+            Err(error.message)
+        }
+        Ok(_) => {
+            tracing::info!(username = %credentials.username, "connection.session.resumed");
+            Ok(())
+        }
+        Err(err) => {
+            tracing::warn!(
+                error = %err,
+                username = %credentials.username,
+                "connection.session.resume_failed"
+            );
+            Err(err.to_string())
+        }
+    }
+}
+
+/// Sends a request over the current socket and awaits its response.
+///
+/// Refused with `ConnectionError::Disconnected` while the link is being
+/// re-established or the session resumed on it.
+async fn send_request(
     inner: &Arc<Mutex<ConnectionInner>>,
-    event_tx: &mpsc::UnboundedSender<Value>,
-    text: &str,
-) {
+    state: &watch::Sender<ConnectionState>,
+    action: &str,
+    params: Value,
+) -> Result<Response, ConnectionError> {
+    if matches!(
+        *state.borrow(),
+        ConnectionState::Reconnecting | ConnectionState::Resuming
+    ) {
+        return Err(ConnectionError::Disconnected);
+    }
+    send_on_link(inner, action, params).await
+}
+
+/// Sends a request over the current socket, whatever the link state, and
+/// awaits its response.
+async fn send_on_link(
+    inner: &Arc<Mutex<ConnectionInner>>,
+    action: &str,
+    params: Value,
+) -> Result<Response, ConnectionError> {
+    let (msg_id, rx, write_tx, recorder, timeout) = {
+        let mut inner = inner.lock().await;
+        let Some(write_tx) = inner.write_tx.clone() else {
+            return Err(ConnectionError::NotConnected);
+        };
+        let id = format!("msg-{:04}", inner.next_id);
+        inner.next_id += 1;
+        let (tx, rx) = oneshot::channel();
+        inner.pending.insert(id.clone(), tx);
+        (
+            id,
+            rx,
+            write_tx,
+            inner.recorder.clone(),
+            inner.response_timeout,
+        )
+    };
+
+    let request = Request {
+        id: &msg_id,
+        action,
+        params: &params,
+    };
+    let payload = serde_json::to_string(&request)?;
+    if let Some(recorder) = recorder {
+        recorder.record(Direction::Sent, &serde_json::to_value(&request)?);
+    }
+
+    let msg = Message::Text(payload.into());
+    if write_tx.send(msg).await.is_err() {
+        inner.lock().await.pending.remove(&msg_id);
+        return Err(ConnectionError::NotConnected);
+    }
+
+    tracing::debug!(
+        msg.id = %msg_id,
+        msg.action = action,
+        "connection.command.sent"
+    );
+
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(_)) => Err(ConnectionError::ChannelClosed),
+        Err(_) => {
+            // Remove the stale pending entry.
+            let mut inner = inner.lock().await;
+            inner.pending.remove(&msg_id);
+            Err(ConnectionError::Timeout(timeout))
+        }
+    }
+}
+
+/// Routes an incoming WebSocket text message to either a pending request
+/// or the event sinks.
+async fn route_message(inner: &Arc<Mutex<ConnectionInner>>, events: &EventSink, text: &str) {
     let value: Value = match serde_json::from_str(text) {
         Ok(v) => v,
         Err(e) => {
@@ -257,17 +814,29 @@ async fn route_message(
         }
     };
 
+    // Recorded before it is routed, so the log keeps the order the client
+    // saw; the file write happens outside the lock.
+    let recorder = inner.lock().await.recorder.clone();
+    if let Some(recorder) = recorder {
+        recorder.record(Direction::Received, &value);
+    }
+
+    let mut inner = inner.lock().await;
     // If the message has an "id" field, it is a response to a pending request.
     if let Some(id) = value.get("id").and_then(Value::as_str) {
-        let mut inner = inner.lock().await;
         if let Some(tx) = inner.pending.remove(id) {
-            let _ = tx.send(value);
+            let response = serde_json::from_value(value).map_err(ConnectionError::InvalidJson);
+            let _ = tx.send(response);
             return;
         }
     }
 
+    drop(inner);
+
     // Otherwise treat it as a push event.
-    let _ = event_tx.send(value);
+    let event = PushEvent::from_message(value);
+    tracing::debug!(event.kind = event.kind(), "connection.event.received");
+    events.emit(event);
 }
 
 /// Extracts the host (with optional port) from a URL string.
@@ -288,17 +857,179 @@ mod tests {
     #[test]
     fn connection_starts_disconnected() {
         let (event_tx, _rx) = mpsc::unbounded_channel();
-        let conn = GameConnection::new(event_tx);
+        let conn = GameConnection::new(event_tx, ConnectionOptions::default());
         assert!(!conn.is_connected());
     }
 
     #[tokio::test]
     async fn send_command_while_disconnected_returns_error() {
         let (event_tx, _rx) = mpsc::unbounded_channel();
-        let conn = GameConnection::new(event_tx);
+        let conn = GameConnection::new(event_tx, ConnectionOptions::default());
         let result = conn.send_command("look", serde_json::json!({})).await;
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(matches!(err, ConnectionError::NotConnected));
     }
+
+    #[test]
+    fn reconnect_delay_backs_off_exponentially_and_caps() {
+        assert_eq!(reconnect_delay(0), INITIAL_RECONNECT_DELAY);
+        assert_eq!(reconnect_delay(1), INITIAL_RECONNECT_DELAY * 2);
+        assert_eq!(reconnect_delay(3), INITIAL_RECONNECT_DELAY * 8);
+        assert_eq!(reconnect_delay(MAX_RECONNECT_ATTEMPTS), MAX_RECONNECT_DELAY);
+        assert_eq!(reconnect_delay(u32::MAX), MAX_RECONNECT_DELAY);
+    }
+
+    /// Reads the next text frame from a server-side socket as JSON.
+    async fn next_json(ws: &mut WebSocketStream<TcpStream>) -> Value {
+        loop {
+            match ws.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    return serde_json::from_str(&text).expect("client sends JSON");
+                }
+                Some(Ok(_)) => {}
+                other => panic!("socket closed unexpectedly: {other:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn dropped_socket_fails_pending_and_resumes_session() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind should succeed");
+        let url = format!("ws://{}/ws", listener.local_addr().expect("local addr"));
+
+        let (resume_tx, resume_rx) = oneshot::channel();
+        let (answer_tx, answer_rx) = oneshot::channel::<()>();
+        let server = tokio::spawn(async move {
+            // First socket: swallow one command, then drop the link.
+            let (stream, _) = listener.accept().await.expect("accept");
+            let mut ws = tokio_tungstenite::accept_async(stream)
+                .await
+                .expect("handshake");
+            let request = next_json(&mut ws).await;
+            assert_eq!(request["action"], "look");
+            drop(ws);
+
+            // Second socket: the client should re-authenticate on its own,
+            // and hold other commands until the server has answered.
+            let (stream, _) = listener.accept().await.expect("accept");
+            let mut ws = tokio_tungstenite::accept_async(stream)
+                .await
+                .expect("handshake");
+            let resume = next_json(&mut ws).await;
+            let id = resume["id"].clone();
+            resume_tx.send(resume).expect("test is waiting");
+            answer_rx.await.expect("test lets the resume through");
+            let reply = serde_json::json!({ "id": id, "room": "Town Square" });
+            ws.send(Message::Text(reply.to_string().into()))
+                .await
+                .expect("send should succeed");
+            ws
+        });
+
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut conn = GameConnection::new(event_tx, ConnectionOptions::default());
+        let monitor = conn.monitor();
+        let mut feed = conn.subscribe();
+        conn.connect(&url).await.expect("connect should succeed");
+        assert_eq!(monitor.state(), ConnectionState::Connected);
+        conn.set_credentials("aria", "secret").await;
+
+        let started = std::time::Instant::now();
+        let result = conn.send_command("look", serde_json::json!({})).await;
+        assert!(matches!(result, Err(ConnectionError::Disconnected)));
+        assert!(started.elapsed() < DEFAULT_RESPONSE_TIMEOUT);
+
+        let lost = event_rx.recv().await.expect("connection_lost event");
+        assert!(matches!(lost, PushEvent::ConnectionLost(_)));
+        let live = feed.recv().await.expect("live feed should see the event");
+        assert_eq!(live, lost);
+
+        let resume = tokio::time::timeout(Duration::from_secs(5), resume_rx)
+            .await
+            .expect("client should reconnect")
+            .expect("server task should succeed");
+        assert_eq!(resume["action"], "connect");
+        assert_eq!(resume["params"]["username"], "aria");
+        assert_eq!(resume["params"]["token"], "secret");
+        assert_eq!(monitor.state(), ConnectionState::Resuming);
+        let result = conn.send_command("look", serde_json::json!({})).await;
+        assert!(matches!(result, Err(ConnectionError::Disconnected)));
+
+        answer_tx.send(()).expect("server is waiting");
+        let restored = feed.recv().await.expect("connection_restored event");
+        assert!(matches!(restored, PushEvent::ConnectionRestored(_)));
+        assert_eq!(monitor.state(), ConnectionState::Connected);
+        let _ws = server.await.expect("server task should succeed");
+
+        conn.disconnect().await;
+        assert_eq!(monitor.state(), ConnectionState::Closed);
+    }
+
+    #[tokio::test]
+    async fn refused_resume_closes_the_link() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind should succeed");
+        let url = format!("ws://{}/ws", listener.local_addr().expect("local addr"));
+
+        let server = tokio::spawn(async move {
+            // First socket: drop the link straight away.
+            let (stream, _) = listener.accept().await.expect("accept");
+            drop(
+                tokio_tungstenite::accept_async(stream)
+                    .await
+                    .expect("handshake"),
+            );
+
+            // Second socket: refuse to resume the session.
+            let (stream, _) = listener.accept().await.expect("accept");
+            let mut ws = tokio_tungstenite::accept_async(stream)
+                .await
+                .expect("handshake");
+            let resume = next_json(&mut ws).await;
+            assert_eq!(resume["action"], "connect");
+            let reply = serde_json::json!({
+                "id": resume["id"],
+                "error": { "code": "auth_failed", "message": "Token expired." }
+            });
+            ws.send(Message::Text(reply.to_string().into()))
+                .await
+                .expect("send should succeed");
+            ws
+        });
+
+        let (event_tx, _rx) = mpsc::unbounded_channel();
+        let mut conn = GameConnection::new(event_tx, ConnectionOptions::default());
+        let mut monitor = conn.monitor();
+        let mut feed = conn.subscribe();
+        conn.connect(&url).await.expect("connect should succeed");
+        conn.set_credentials("aria", "stale").await;
+
+        let lost = feed.recv().await.expect("connection_lost event");
+        assert!(matches!(lost, PushEvent::ConnectionLost(_)));
+        let refused = tokio::time::timeout(Duration::from_secs(5), feed.recv())
+            .await
+            .expect("client should reconnect")
+            .expect("second event");
+        let PushEvent::ConnectionLost(notice) = refused else {
+            panic!("expected connection_lost, got {refused:?}");
+        };
+        assert!(notice.message.contains("Token expired."));
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            monitor
+                .state
+                .wait_for(|state| *state == ConnectionState::Closed),
+        )
+        .await
+        .expect("link should close")
+        .expect("connection is alive");
+        let result = conn.send_command("look", serde_json::json!({})).await;
+        assert!(matches!(result, Err(ConnectionError::NotConnected)));
+        let _ws = server.await.expect("server task should succeed");
+    }
 }
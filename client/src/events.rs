@@ -4,9 +4,10 @@
 //! between MCP tool calls. Events are drained and included with each
 //! tool response so Claude can narrate them naturally.
 
-use serde_json::Value;
 use tokio::sync::mpsc;
 
+use crate::protocol::{Notice, PushEvent};
+
 /// Maximum events to buffer before producing an overflow warning.
 ///
 /// Prevents unbounded memory growth if the player is idle for
@@ -19,21 +20,26 @@ const MAX_BUFFERED_EVENTS: usize = 200;
 /// drained in bulk when a tool response is being assembled.
 #[derive(Debug)]
 pub struct EventBuffer {
-    rx: mpsc::UnboundedReceiver<Value>,
+    rx: mpsc::UnboundedReceiver<PushEvent>,
 }
 
 impl EventBuffer {
     /// Creates a new event buffer from the given channel receiver.
-    pub fn new(rx: mpsc::UnboundedReceiver<Value>) -> Self {
+    pub fn new(rx: mpsc::UnboundedReceiver<PushEvent>) -> Self {
         Self { rx }
     }
 
+    /// Waits for the next event; `None` once the connection is gone.
+    pub async fn recv(&mut self) -> Option<PushEvent> {
+        self.rx.recv().await
+    }
+
     /// Drains all buffered events, returning them as a vector.
     ///
     /// If more than 200 events have accumulated, the excess is
     /// discarded and a synthetic `events_overflow` event is appended
     /// to signal that some events were lost.
-    pub fn drain(&mut self) -> Vec<Value> {
+    pub fn drain(&mut self) -> Vec<PushEvent> {
         let mut events = Vec::new();
 
         while let Ok(event) = self.rx.try_recv() {
@@ -44,15 +50,9 @@ impl EventBuffer {
                 while self.rx.try_recv().is_ok() {
                     overflow_count += 1;
                 }
-                events.push(serde_json::json!({
-                    "type": "event",
-                    "data": {
-                        "event": "events_overflow",
-                        "message": format!(
-                            "{overflow_count} events were dropped due to buffer overflow"
-                        )
-                    }
-                }));
+                events.push(PushEvent::EventsOverflow(Notice::new(format!(
+                    "{overflow_count} events were dropped due to buffer overflow"
+                ))));
                 break;
             }
         }
@@ -78,10 +78,14 @@ mod tests {
         let (tx, rx) = mpsc::unbounded_channel();
         let mut buffer = EventBuffer::new(rx);
 
-        tx.send(serde_json::json!({"type": "event", "data": {"event": "player_entered"}}))
-            .expect("send should succeed");
-        tx.send(serde_json::json!({"type": "event", "data": {"event": "combat_update"}}))
-            .expect("send should succeed");
+        tx.send(PushEvent::from_message(serde_json::json!(
+            {"type": "event", "data": {"event": "player_entered", "player": "Brom"}}
+        )))
+        .expect("send should succeed");
+        tx.send(PushEvent::from_message(serde_json::json!(
+            {"type": "event", "data": {"event": "combat_update"}}
+        )))
+        .expect("send should succeed");
 
         let events = buffer.drain();
         assert_eq!(events.len(), 2);
@@ -93,8 +97,10 @@ mod tests {
         let mut buffer = EventBuffer::new(rx);
 
         for i in 0..250 {
-            tx.send(serde_json::json!({"type": "event", "data": {"index": i}}))
-                .expect("send should succeed");
+            tx.send(PushEvent::from_message(
+                serde_json::json!({"type": "event", "data": {"index": i}}),
+            ))
+            .expect("send should succeed");
         }
 
         let events = buffer.drain();
@@ -102,10 +108,6 @@ mod tests {
         assert_eq!(events.len(), MAX_BUFFERED_EVENTS + 1);
 
         let last = events.last().expect("should have events");
-        let event_name = last
-            .get("data")
-            .and_then(|d| d.get("event"))
-            .and_then(|e| e.as_str());
-        assert_eq!(event_name, Some("events_overflow"));
+        assert_eq!(last.kind(), "events_overflow");
     }
 }
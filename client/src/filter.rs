@@ -0,0 +1,188 @@
+//! Which buffered events a tool response reports in full.
+//!
+//! Busy zones produce a steady stream of arrivals, departures and
+//! shouts that would otherwise crowd every tool response. Each session
+//! has an [`EventFilter`]: a verbosity level picks the event types worth
+//! reporting, and hide rules drop events by type, chat channel, player
+//! or room on top of that. Hidden events are not lost silently; the
+//! response counts them by type under `events_hidden`.
+//!
+//! Filtering only shapes what is reported. Triggers, urgent-event
+//! notifications, observers and the interrupt checks of `travel_to` and
+//! `alias_run` still see every event; `batch` sees what its steps report.
+
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::protocol::PushEvent;
+use crate::triggers::TRIGGER_FIRED_EVENT;
+
+/// Event types reported at [`Verbosity::Minimal`]: those that need a
+/// reaction, and the client's own notices.
+const MINIMAL_EVENTS: &[&str] = &[
+    "combat_update",
+    "death",
+    "tell",
+    "party_invite",
+    "level_up",
+    "events_overflow",
+    "connection_lost",
+    "connection_restored",
+    TRIGGER_FIRED_EVENT,
+];
+
+/// Ambient event types only reported at [`Verbosity::Everything`].
+const AMBIENT_EVENTS: &[&str] = &["player_entered", "player_left", "shout"];
+
+/// Event data fields naming the player a message or movement is from.
+///
+/// Combat updates are left out on purpose: hiding a player's chatter
+/// must not hide the fact that they are attacking you.
+const PLAYER_FIELDS: &[&str] = &["from", "player"];
+
+/// Event data fields naming the room an event happened in.
+const ROOM_FIELDS: &[&str] = &["room", "room_id"];
+
+/// How many event types a tool response reports in full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Verbosity {
+    /// Only events that need a reaction (combat, death, tells, invites,
+    /// level-ups) and connection notices.
+    Minimal,
+    /// Everything except ambient arrivals, departures and shouts.
+    Normal,
+    /// Every event, as reported before filters existed.
+    #[default]
+    Everything,
+}
+
+/// Rules deciding which events are reported and which only counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventFilter {
+    /// Which event types are reported at all.
+    pub verbosity: Verbosity,
+    /// Event types to hide (e.g. "say").
+    pub hide_events: Vec<String>,
+    /// Chat channels to hide (e.g. "trade").
+    pub hide_channels: Vec<String>,
+    /// Players whose messages and movements to hide.
+    pub hide_players: Vec<String>,
+    /// Rooms (by name or ID) whose events to hide.
+    pub hide_rooms: Vec<String>,
+}
+
+/// Events split by an [`EventFilter`].
+#[derive(Debug, Default)]
+pub struct Filtered<'a> {
+    /// Events to report in full.
+    pub shown: Vec<&'a PushEvent>,
+    /// Number of hidden events by type.
+    pub hidden: BTreeMap<String, usize>,
+}
+
+impl EventFilter {
+    /// Returns true if `event` should be reported in full.
+    pub fn shows(&self, event: &PushEvent) -> bool {
+        let kind = event.kind();
+        let by_verbosity = match self.verbosity {
+            Verbosity::Minimal => MINIMAL_EVENTS.contains(&kind),
+            Verbosity::Normal => !AMBIENT_EVENTS.contains(&kind),
+            Verbosity::Everything => true,
+        };
+        if !by_verbosity || self.hide_events.iter().any(|hidden| hidden == kind) {
+            return false;
+        }
+
+        let message = event.to_message();
+        let data = &message["data"];
+        let field_in = |fields: &[&str], names: &[String]| {
+            fields
+                .iter()
+                .filter_map(|field| data.get(*field).and_then(Value::as_str))
+                .any(|value| names.iter().any(|name| name.eq_ignore_ascii_case(value)))
+        };
+        !(field_in(&["channel"], &self.hide_channels)
+            || field_in(PLAYER_FIELDS, &self.hide_players)
+            || field_in(ROOM_FIELDS, &self.hide_rooms))
+    }
+
+    /// Splits `events` into those to report and counts of the rest.
+    pub fn apply<'a>(&self, events: &'a [PushEvent]) -> Filtered<'a> {
+        let mut filtered = Filtered::default();
+        for event in events {
+            if self.shows(event) {
+                filtered.shown.push(event);
+            } else {
+                *filtered.hidden.entry(event.kind().to_owned()).or_default() += 1;
+            }
+        }
+        filtered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn event(data: &Value) -> PushEvent {
+        PushEvent::from_message(json!({ "type": "event", "data": data }))
+    }
+
+    #[test]
+    fn verbosity_and_rules_hide_and_count_events() {
+        let events = [
+            event(&json!({ "event": "player_entered", "player": "Brom" })),
+            event(&json!({ "event": "say", "from": "Brom", "message": "hi" })),
+            event(
+                &json!({ "event": "channel", "from": "Cato", "message": "wts", "channel": "Trade" }),
+            ),
+            event(&json!({ "event": "tell", "from": "Spammer", "message": "buy gold" })),
+            event(&json!({ "event": "combat_update", "attacker": "Goblin", "hp": 7 })),
+        ];
+        let kinds = |filter: &EventFilter| -> Vec<&str> {
+            filter
+                .apply(&events)
+                .shown
+                .iter()
+                .map(|e| e.kind())
+                .collect()
+        };
+
+        let mut filter = EventFilter::default();
+        assert_eq!(
+            kinds(&filter),
+            ["player_entered", "say", "channel", "tell", "combat_update"]
+        );
+
+        filter.verbosity = Verbosity::Normal;
+        assert_eq!(kinds(&filter), ["say", "channel", "tell", "combat_update"]);
+
+        // Hiding a player hides their chatter but not their attacks.
+        filter.verbosity = Verbosity::Everything;
+        filter.hide_channels = vec!["trade".to_owned()];
+        filter.hide_players = vec!["spammer".to_owned(), "goblin".to_owned()];
+        assert_eq!(kinds(&filter), ["player_entered", "say", "combat_update"]);
+
+        let minimal = EventFilter {
+            verbosity: Verbosity::Minimal,
+            ..EventFilter::default()
+        };
+        let filtered = minimal.apply(&events);
+        assert_eq!(filtered.shown.len(), 2);
+        assert_eq!(
+            filtered.hidden,
+            BTreeMap::from([
+                ("channel".to_owned(), 1),
+                ("player_entered".to_owned(), 1),
+                ("say".to_owned(), 1),
+            ])
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! WebSocket keepalive and round-trip latency tracking.
+//!
+//! The writer task sends a WebSocket ping on a fixed interval so NAT
+//! devices and load balancers see traffic on idle sessions. The reader
+//! task matches pongs against the outstanding ping to measure latency,
+//! and declares the peer dead if a ping goes unanswered for too long.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Interval between keepalive pings.
+///
+/// Well under the common 60-second idle cutoff of NAT gateways
+/// and cloud load balancers.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long a ping may go unanswered before the peer is considered dead.
+///
+/// Three missed intervals, so a single delayed pong does not
+/// tear down a healthy session.
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Number of round-trip samples kept for the rolling latency statistics.
+const LATENCY_WINDOW: usize = 10;
+
+/// Keepalive state shared between the writer and reader tasks.
+#[derive(Debug, Default)]
+pub struct Heartbeat {
+    next_nonce: u64,
+    outstanding: Option<(u64, Instant)>,
+    samples: VecDeque<Duration>,
+    last_pong: Option<Instant>,
+}
+
+impl Heartbeat {
+    /// Forgets any outstanding ping, e.g. after the socket was replaced.
+    ///
+    /// Latency samples are kept so the statistics survive a reconnect.
+    pub fn reset(&mut self) {
+        self.outstanding = None;
+    }
+
+    /// Records a ping sent at `now` and returns its payload.
+    ///
+    /// While an earlier ping is still unanswered it stays the outstanding
+    /// one, so dead-peer detection measures from the oldest missing pong.
+    pub fn ping_sent(&mut self, now: Instant) -> [u8; 8] {
+        let nonce = self.next_nonce;
+        self.next_nonce = self.next_nonce.wrapping_add(1);
+        if self.outstanding.is_none() {
+            self.outstanding = Some((nonce, now));
+        }
+        nonce.to_be_bytes()
+    }
+
+    /// Records a pong received at `now`, returning the round-trip time
+    /// if it answers the outstanding ping.
+    ///
+    /// Any pong proves the peer is alive, but peers may skip replies to
+    /// superseded pings, so only an exact match yields a latency sample.
+    pub fn pong_received(&mut self, payload: &[u8], now: Instant) -> Option<Duration> {
+        self.last_pong = Some(now);
+        let (expected, sent_at) = self.outstanding.take()?;
+        let nonce = u64::from_be_bytes(payload.try_into().ok()?);
+        if nonce != expected {
+            return None;
+        }
+
+        let rtt = now.saturating_duration_since(sent_at);
+        if self.samples.len() == LATENCY_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(rtt);
+        Some(rtt)
+    }
+
+    /// Returns true if the outstanding ping has gone unanswered past the timeout.
+    pub fn is_overdue(&self, now: Instant) -> bool {
+        self.outstanding
+            .is_some_and(|(_, sent_at)| now.saturating_duration_since(sent_at) > HEARTBEAT_TIMEOUT)
+    }
+
+    /// Summarises the rolling latency window.
+    pub fn latency(&self, now: Instant) -> LatencyStats {
+        let count = self.samples.len();
+        let total: Duration = self.samples.iter().sum();
+        LatencyStats {
+            last_ms: self.samples.back().map(duration_ms),
+            avg_ms: u32::try_from(count)
+                .ok()
+                .filter(|&n| n > 0)
+                .map(|n| duration_ms(&(total / n))),
+            min_ms: self.samples.iter().min().map(duration_ms),
+            max_ms: self.samples.iter().max().map(duration_ms),
+            samples: count,
+            last_pong_ago_ms: self
+                .last_pong
+                .map(|at| duration_ms(&now.saturating_duration_since(at))),
+        }
+    }
+}
+
+/// Rolling round-trip latency over the last few heartbeats.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyStats {
+    /// Most recent round-trip time.
+    pub last_ms: Option<u64>,
+    /// Mean round-trip time across the window.
+    pub avg_ms: Option<u64>,
+    /// Fastest round-trip time in the window.
+    pub min_ms: Option<u64>,
+    /// Slowest round-trip time in the window.
+    pub max_ms: Option<u64>,
+    /// Number of samples in the window.
+    pub samples: usize,
+    /// Time since the server last answered a ping.
+    pub last_pong_ago_ms: Option<u64>,
+}
+
+/// Converts a duration to whole milliseconds, saturating on overflow.
+fn duration_ms(duration: &Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pong_records_round_trip_time() {
+        let mut heartbeat = Heartbeat::default();
+        let start = Instant::now();
+        let payload = heartbeat.ping_sent(start);
+
+        let rtt = heartbeat.pong_received(&payload, start + Duration::from_millis(42));
+        assert_eq!(rtt, Some(Duration::from_millis(42)));
+
+        let stats = heartbeat.latency(start + Duration::from_millis(50));
+        assert_eq!(stats.last_ms, Some(42));
+        assert_eq!(stats.samples, 1);
+        assert_eq!(stats.last_pong_ago_ms, Some(8));
+    }
+
+    #[test]
+    fn latency_window_is_bounded() {
+        let mut heartbeat = Heartbeat::default();
+        let start = Instant::now();
+        for i in 0..20 {
+            let sent = start + Duration::from_secs(i);
+            let payload = heartbeat.ping_sent(sent);
+            heartbeat.pong_received(&payload, sent + Duration::from_millis(10 * (i + 1)));
+        }
+
+        let stats = heartbeat.latency(start);
+        assert_eq!(stats.samples, LATENCY_WINDOW);
+        assert_eq!(stats.min_ms, Some(110));
+        assert_eq!(stats.max_ms, Some(200));
+        assert_eq!(stats.avg_ms, Some(155));
+    }
+
+    #[test]
+    fn unanswered_ping_becomes_overdue() {
+        let mut heartbeat = Heartbeat::default();
+        let start = Instant::now();
+        heartbeat.ping_sent(start);
+        // A later ping must not push the deadline back.
+        heartbeat.ping_sent(start + HEARTBEAT_INTERVAL);
+
+        assert!(!heartbeat.is_overdue(start + HEARTBEAT_TIMEOUT));
+        assert!(heartbeat.is_overdue(start + HEARTBEAT_TIMEOUT + Duration::from_secs(1)));
+
+        heartbeat.reset();
+        assert!(!heartbeat.is_overdue(start + HEARTBEAT_TIMEOUT * 2));
+    }
+}
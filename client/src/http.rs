@@ -0,0 +1,206 @@
+//! MCP over streamable HTTP, so several hosts can share one game session.
+//!
+//! With `--transport http --listen <ADDR>`, the MCP endpoint is served at
+//! `http://<ADDR>/mcp` instead of on stdio. Every MCP client attached
+//! there drives the same character sessions, event buffers and world
+//! state. Each one receives tool list changes and urgent events, and
+//! updates to the resources it subscribed to.
+//!
+//! The endpoint only listens on loopback addresses. Every request must
+//! still carry `Authorization: Bearer <token>`, since any local process
+//! or web page could otherwise reach it and play the character. A
+//! generated token is written to a file only its owner can read rather
+//! than printed, so it stays out of terminal logs.
+
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::streamable_http_server::{StreamableHttpServerConfig, StreamableHttpService};
+use tokio::net::TcpListener;
+
+use crate::tools::GameHandler;
+
+/// Environment variable holding the bearer token for the HTTP transport.
+pub const TOKEN_ENV: &str = "WYVERN_HTTP_TOKEN";
+
+/// File under the token path that a generated bearer token is written to.
+pub const TOKEN_FILE: &str = "http-token";
+
+/// Returns a fresh random bearer token.
+pub fn generate_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Writes `token` to `path`, readable and writable by its owner only.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written.
+pub fn write_token_file(path: &Path, token: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // Recreated rather than truncated, so an old file's looser mode
+    // does not carry over.
+    let _ = std::fs::remove_file(path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    writeln!(file, "{token}")
+}
+
+/// Serves `handler` over streamable HTTP on `addr`; port 0 picks a free port.
+///
+/// Requests without `Authorization: Bearer <token>` are refused with 401.
+/// Returns the endpoint URL once the listener is bound; the server runs
+/// in the background for the life of the process.
+///
+/// # Errors
+///
+/// Returns an error if `addr` is not a loopback address or cannot be
+/// bound.
+pub async fn start(
+    handler: GameHandler,
+    addr: SocketAddr,
+    token: String,
+) -> std::io::Result<String> {
+    if !addr.ip().is_loopback() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("HTTP address {addr} is not a loopback address"),
+        ));
+    }
+    let service = StreamableHttpService::new(
+        move || Ok(handler.for_client()),
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig::default(),
+    );
+    let router =
+        axum::Router::new()
+            .nest_service("/mcp", service)
+            .layer(middleware::from_fn_with_state(
+                Arc::<str>::from(token),
+                require_bearer,
+            ));
+
+    let listener = TcpListener::bind(addr).await?;
+    let url = format!("http://{}/mcp", listener.local_addr()?);
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, router).await {
+            tracing::error!(error = %err, "http.server.failed");
+        }
+    });
+    tracing::info!(http.url = %url, "http.listening");
+    Ok(url)
+}
+
+/// Rejects requests that do not present the expected bearer token.
+async fn require_bearer(State(token): State<Arc<str>>, request: Request, next: Next) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match presented {
+        Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => {
+            tracing::warn!(http.path = %request.uri().path(), "http.unauthorized");
+            (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                "missing or invalid bearer token",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Compares two byte strings without an early exit on the first mismatch.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    use super::*;
+    use crate::tools::HandlerOptions;
+
+    /// Sends an MCP `initialize` request and returns the status line.
+    async fn initialize(url: &str, authorization: Option<&str>) -> String {
+        let host = url.trim_start_matches("http://").trim_end_matches("/mcp");
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"0"}}}"#;
+        let auth =
+            authorization.map_or_else(String::new, |value| format!("Authorization: {value}\r\n"));
+        let request = format!(
+            "POST /mcp HTTP/1.1\r\nHost: {host}\r\n{auth}Content-Type: application/json\r\n\
+             Accept: application/json, text/event-stream\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let mut stream = TcpStream::connect(host).await.expect("connect");
+        stream.write_all(request.as_bytes()).await.expect("send");
+        let mut response = vec![0; 64];
+        let read = stream.read(&mut response).await.expect("read");
+        String::from_utf8_lossy(&response[..read])
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_owned()
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_bearer_token() {
+        let handler = GameHandler::new(
+            "ws://127.0.0.1:9/ws".to_owned(),
+            std::env::temp_dir().display().to_string(),
+            HandlerOptions::default(),
+        );
+        let refused = start(handler.clone(), ([0, 0, 0, 0], 0).into(), "x".to_owned()).await;
+        assert_eq!(
+            refused.expect_err("not loopback").kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        let url = start(handler, ([127, 0, 0, 1], 0).into(), "letmein".to_owned())
+            .await
+            .expect("listening");
+
+        assert!(initialize(&url, None).await.contains("401"));
+        assert!(initialize(&url, Some("Bearer nope")).await.contains("401"));
+        assert!(initialize(&url, Some("Bearer letmein"))
+            .await
+            .contains("200"));
+    }
+
+    #[test]
+    fn generated_tokens_are_written_for_the_owner_only() {
+        let dir = std::env::temp_dir().join(format!("wyvern-http-{}", std::process::id()));
+        let path = dir.join(TOKEN_FILE);
+        write_token_file(&path, "first").expect("written");
+        write_token_file(&path, "second").expect("rewritten");
+        assert_eq!(std::fs::read_to_string(&path).expect("read"), "second\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path)
+                .expect("metadata")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
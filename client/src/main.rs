@@ -1,19 +1,42 @@
 //! Weights & Wyverns MCP server entry point.
 //!
-//! Runs a local MCP server over stdio that bridges Claude Code
-//! to the centralized game server via WebSocket. All game logic
-//! lives on the server; this binary is a thin transport layer.
+//! Runs a local MCP server over stdio (or streamable HTTP) that bridges
+//! Claude Code to the centralized game server via WebSocket. All game
+//! logic lives on the server; this binary is a thin transport layer.
 
 use mimalloc::MiMalloc;
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
+mod aliases;
+mod config;
 mod connection;
 mod events;
+mod filter;
+mod heartbeat;
+mod http;
+mod manifest;
+mod mock;
+mod notify;
+mod observer;
+mod play;
+mod prompts;
+mod protocol;
+mod recorder;
+mod replay;
+mod resources;
+mod sessions;
+mod store;
+mod tokens;
 mod tools;
+mod triggers;
+mod tui;
+mod world;
 
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
 use rmcp::ServiceExt;
 use tracing_subscriber::EnvFilter;
 
@@ -21,13 +44,145 @@ use tracing_subscriber::EnvFilter;
 #[derive(Debug, Parser)]
 #[command(name = "ww-client", version, about)]
 struct Args {
-    /// WebSocket URL of the game server.
-    #[arg(long, default_value = "ws://localhost:8080/ws")]
-    server: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Config file with settings and named profiles.
+    #[arg(long, env = "WYVERN_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Profile from the config file to use.
+    #[arg(long, env = "WYVERN_PROFILE")]
+    profile: Option<String>,
+
+    /// WebSocket URL of the game server [default: `ws://localhost:8080/ws`].
+    #[arg(long)]
+    server: Option<String>,
+
+    /// Base directory for authentication token storage
+    /// [default: ~/.weights-and-wyverns].
+    #[arg(long)]
+    token_path: Option<String>,
+
+    /// Character `connect` logs in as when no username is given.
+    #[arg(long)]
+    username: Option<String>,
+
+    /// Seconds to wait for the server to answer a command [default: 30].
+    #[arg(long, value_name = "SECS")]
+    response_timeout: Option<u64>,
+
+    /// Expose the `raw_command` tool for sending arbitrary server actions.
+    ///
+    /// Intended for exercising new server features and debugging the
+    /// protocol; leave off for normal play.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    allow_raw: Option<bool>,
+
+    /// Push urgent game events to the MCP client as soon as they arrive,
+    /// instead of only with the next tool response.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    push_events: Option<bool>,
+
+    /// Comma-separated event types treated as urgent by `--push-events`
+    /// [default: `combat_update,tell,party_invite,death`].
+    #[arg(long, value_delimiter = ',')]
+    urgent_events: Option<Vec<String>>,
+
+    /// Record every command, response and event of each session as JSON
+    /// Lines under `<token-path>/sessions/<username>/`.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    record: Option<bool>,
+
+    /// Mirror every command, response and event to read-only observers
+    /// over a WebSocket on this loopback address (e.g. `127.0.0.1:7777`).
+    #[arg(long, value_name = "ADDR")]
+    observe: Option<std::net::SocketAddr>,
+
+    /// How MCP hosts reach this server.
+    #[arg(long, value_enum, default_value_t = Transport::Stdio)]
+    transport: Transport,
+
+    /// Loopback address for `--transport http` to listen on
+    /// (e.g. `127.0.0.1:8931`).
+    #[arg(long, value_name = "ADDR", required_if_eq("transport", "http"))]
+    listen: Option<std::net::SocketAddr>,
+
+    /// Bearer token HTTP clients must send; if unset, a random one is
+    /// generated and written to `<token-path>/http-token`.
+    #[arg(long, env = http::TOKEN_ENV, hide_env_values = true)]
+    http_token: Option<String>,
+}
+
+/// MCP transport served to hosts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Transport {
+    /// A single host talking over stdin/stdout.
+    Stdio,
+    /// Any number of hosts sharing one game session over streamable HTTP.
+    Http,
+}
+
+impl Args {
+    /// Returns the settings given on the command line.
+    fn layer(&self) -> config::Layer {
+        config::Layer {
+            server: self.server.clone(),
+            token_path: self.token_path.clone(),
+            username: self.username.clone(),
+            response_timeout_secs: self.response_timeout,
+            allow_raw: self.allow_raw,
+            push_events: self.push_events,
+            urgent_events: self.urgent_events.clone(),
+            record: self.record,
+            observe: self.observe,
+            event_filter: None,
+            event_filters: None,
+        }
+    }
+}
+
+/// Alternative modes; without one, the MCP server talks to `--server`.
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Serve MCP against a recorded session instead of a live server.
+    ///
+    /// Requests are answered from the transcript and its push events are
+    /// re-sent on their original timing. Tokens, maps, triggers and
+    /// aliases go to a temporary directory rather than the token path.
+    Replay {
+        /// Transcript written by `--record`.
+        file: PathBuf,
+    },
+
+    /// Run a local mock game server for offline development.
+    ///
+    /// Serves a tiny scripted world; point `--server` at the printed URL.
+    MockServer {
+        /// Port to listen on.
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+
+    /// Play from the terminal instead of serving MCP.
+    ///
+    /// Type commands such as `n`, `attack goblin` or `say hi`; `help`
+    /// lists them all.
+    Play {
+        /// Character to log in as [default: the configured username].
+        #[arg(long)]
+        username: Option<String>,
+    },
 
-    /// Base directory for authentication token storage.
-    #[arg(long, default_value = "~/.weights-and-wyverns")]
-    token_path: String,
+    /// Play in a full-screen terminal client.
+    ///
+    /// Shows the room, an event log, HP and mana bars and the party
+    /// beside a command line that takes the same commands as `play`.
+    Tui {
+        /// Character to log in as [default: the configured username].
+        #[arg(long)]
+        username: Option<String>,
+    },
 }
 
 #[tokio::main]
@@ -45,15 +200,132 @@ async fn main() -> anyhow::Result<()> {
 
     let args = Args::parse();
 
+    let config_path = match &args.config {
+        Some(path) => path.clone(),
+        None => PathBuf::from(tools::expand_tilde(config::DEFAULT_CONFIG_PATH)),
+    };
+    let mut settings = config::ConfigFile::load(&config_path, args.config.is_some())?
+        .resolve(args.profile.as_deref())?
+        .merge(args.layer())
+        .finish();
+
+    // Storage that only lives as long as this process.
+    let mut scratch = None;
+    let server = match &args.command {
+        Some(Command::Replay { file }) => {
+            let transcript = replay::Transcript::load(file)?;
+            let addr = replay::serve(transcript).await?;
+            // Replayed tokens are redacted and the session is not real, so
+            // keep it away from the saved tokens, maps, triggers and aliases.
+            let dir = std::env::temp_dir().join(format!("wyvern-replay-{}", std::process::id()));
+            settings.token_path = dir.display().to_string();
+            scratch = Some(dir);
+            format!("ws://{addr}/ws")
+        }
+        Some(Command::MockServer { port }) => {
+            let addr = std::net::SocketAddr::from(([127, 0, 0, 1], *port));
+            let server = mock::MockServer::start(addr, mock::Script::world()).await?;
+            eprintln!("mock game server listening on {}", server.url());
+            tokio::signal::ctrl_c().await?;
+            return Ok(());
+        }
+        Some(Command::Play { username }) => {
+            let username = username.clone().or(settings.username.clone());
+            return play(settings, username, false).await;
+        }
+        Some(Command::Tui { username }) => {
+            let username = username.clone().or(settings.username.clone());
+            return play(settings, username, true).await;
+        }
+        None => settings.server,
+    };
+
     tracing::info!(
-        server.url = %args.server,
-        token.path = %args.token_path,
+        server.url = %server,
+        token.path = %settings.token_path,
+        config.profile = args.profile.as_deref(),
+        raw.allowed = settings.allow_raw,
+        events.push = settings.push_events,
+        session.record = settings.record,
+        token.encrypted = std::env::var_os(tokens::PASSPHRASE_ENV).is_some(),
         "mcp.server.starting"
     );
 
-    let handler = tools::GameHandler::new(args.server, args.token_path);
-    let service = handler.serve(rmcp::transport::stdio()).await?;
-    service.waiting().await?;
+    let observer = match settings.observe {
+        Some(addr) => Some(observer::Observer::bind(addr).await?),
+        None => None,
+    };
 
+    let options = tools::HandlerOptions {
+        allow_raw: settings.allow_raw,
+        urgent_events: settings
+            .push_events
+            .then(|| notify::UrgentEvents::new(&settings.urgent_events)),
+        record: settings.record,
+        default_username: settings.username,
+        response_timeout: settings.response_timeout,
+        token_passphrase: tokens::Passphrase::from_env(),
+        observer,
+        event_filter: settings.event_filter,
+        event_filters: settings.event_filters,
+    };
+    let token_path = settings.token_path.clone();
+    let handler = tools::GameHandler::new(server, settings.token_path, options);
+    if let (Transport::Http, Some(addr)) = (args.transport, args.listen) {
+        let token = if let Some(token) = args.http_token {
+            token
+        } else {
+            let token = http::generate_token();
+            let path = PathBuf::from(tools::expand_tilde(&token_path)).join(http::TOKEN_FILE);
+            http::write_token_file(&path, &token)?;
+            eprintln!("bearer token written to {}", path.display());
+            token
+        };
+        let url = http::start(handler, addr, token).await?;
+        eprintln!("MCP endpoint listening on {url}");
+        tokio::signal::ctrl_c().await?;
+    } else {
+        let service = handler.serve(rmcp::transport::stdio()).await?;
+        service.waiting().await?;
+    }
+
+    if let Some(dir) = scratch {
+        let _ = std::fs::remove_dir_all(dir);
+    }
     Ok(())
 }
+
+/// Runs `wyvern play`, or the full-screen client if `tui` is set.
+async fn play(
+    settings: config::Settings,
+    username: Option<String>,
+    tui: bool,
+) -> anyhow::Result<()> {
+    let Some(username) = username else {
+        anyhow::bail!("a character is needed: pass --username or set `username` in the config");
+    };
+    let base = PathBuf::from(tools::expand_tilde(&settings.token_path));
+
+    let mut connection = connection::ConnectionOptions::default();
+    if let Some(timeout) = settings.response_timeout {
+        connection.response_timeout = timeout;
+    }
+    if settings.record {
+        let recorder = recorder::SessionRecorder::new(base.join("sessions"));
+        recorder.start(&username);
+        connection.recorder = Some(recorder);
+    }
+
+    let history = base.join("history");
+    let options = play::PlayOptions {
+        server: settings.server,
+        username,
+        tokens: tokens::TokenStore::new(base, tokens::Passphrase::from_env()),
+        connection,
+    };
+    if tui {
+        tui::run(options).await
+    } else {
+        play::run(options, history).await
+    }
+}
@@ -0,0 +1,139 @@
+//! Server-provided tools registered at runtime.
+//!
+//! After connecting, the client asks the server for its tool manifest and
+//! exposes every tool it does not already implement natively. Built-in
+//! tools always win on a name clash, and servers that predate the
+//! manifest simply leave the built-in set in place.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use rmcp::model::Tool;
+use serde_json::{Map, Value};
+
+use crate::protocol::ManifestTool;
+
+/// Server action that returns the tool manifest.
+pub const MANIFEST_ACTION: &str = "tool_manifest";
+
+/// The set of tools currently advertised from the server manifest.
+#[derive(Debug, Clone, Default)]
+pub struct DynamicTools {
+    tools: Arc<RwLock<BTreeMap<String, ManifestTool>>>,
+}
+
+impl DynamicTools {
+    /// Replaces the advertised tools with those from a fresh manifest.
+    ///
+    /// Tools for which `is_builtin` returns true are skipped. Returns true
+    /// if the advertised set changed, i.e. clients should be notified.
+    pub fn replace(&self, manifest: Vec<ManifestTool>, is_builtin: impl Fn(&str) -> bool) -> bool {
+        let mut next = BTreeMap::new();
+        for tool in manifest {
+            if is_builtin(&tool.name) {
+                tracing::debug!(tool.name = %tool.name, "manifest.tool.shadowed");
+                continue;
+            }
+            next.insert(tool.name.clone(), tool);
+        }
+
+        let mut tools = self.tools.write().unwrap_or_else(PoisonError::into_inner);
+        if *tools == next {
+            return false;
+        }
+        *tools = next;
+        true
+    }
+
+    /// Removes all server-provided tools, returning true if any were advertised.
+    pub fn clear(&self) -> bool {
+        self.replace(Vec::new(), |_| false)
+    }
+
+    /// Looks up a server-provided tool by name.
+    pub fn get(&self, name: &str) -> Option<ManifestTool> {
+        self.tools
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(name)
+            .cloned()
+    }
+
+    /// Returns the MCP definitions of all server-provided tools.
+    pub fn list(&self) -> Vec<Tool> {
+        self.tools
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .map(to_tool)
+            .collect()
+    }
+}
+
+/// Converts a manifest entry into an MCP tool definition.
+///
+/// MCP requires an object schema, so a missing `type` is filled in.
+fn to_tool(tool: &ManifestTool) -> Tool {
+    let mut schema: Map<String, Value> = tool.input_schema.clone();
+    schema
+        .entry("type")
+        .or_insert_with(|| Value::String("object".to_owned()));
+    Tool::new(
+        tool.name.clone(),
+        tool.description.clone(),
+        Arc::new(schema),
+    )
+}
+
+impl ManifestTool {
+    /// Returns the server action this tool invokes.
+    pub fn action(&self) -> &str {
+        self.action.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest_tool(name: &str) -> ManifestTool {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "description": format!("Run {name}."),
+        }))
+        .expect("manifest tool parses")
+    }
+
+    #[test]
+    fn replace_skips_builtin_tools() {
+        let tools = DynamicTools::default();
+        let changed = tools.replace(
+            vec![manifest_tool("look"), manifest_tool("craft")],
+            |name| name == "look",
+        );
+        assert!(changed);
+        assert!(tools.get("look").is_none());
+        assert!(tools.get("craft").is_some());
+
+        let listed = tools.list();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].input_schema.get("type"), Some(&"object".into()));
+    }
+
+    #[test]
+    fn replace_reports_only_real_changes() {
+        let tools = DynamicTools::default();
+        assert!(tools.replace(vec![manifest_tool("craft")], |_| false));
+        assert!(!tools.replace(vec![manifest_tool("craft")], |_| false));
+        assert!(tools.clear());
+        assert!(!tools.clear());
+    }
+
+    #[test]
+    fn action_defaults_to_tool_name() {
+        let mut tool = manifest_tool("guild_promote");
+        assert_eq!(tool.action(), "guild_promote");
+        tool.action = Some("guild_rank_set".to_owned());
+        assert_eq!(tool.action(), "guild_rank_set");
+    }
+}
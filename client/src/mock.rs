@@ -0,0 +1,305 @@
+//! Local mock of the game server for offline development and tests.
+//!
+//! Speaks the same `{id, action, params}` protocol as the real server
+//! over a local WebSocket, answering from a [`Script`] of per-action
+//! handlers. `wyvern mock-server` serves [`Script::world`], a tiny world
+//! of two rooms that answers every action the built-in tools send.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Map, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Actions the mock world answers with a plain acknowledgement.
+const ACKNOWLEDGED_ACTIONS: &[&str] = &[
+    "use_ability",
+    "flee",
+    "get",
+    "drop",
+    "equip",
+    "use_item",
+    "say",
+    "tell",
+    "shout",
+    "emote",
+    "who",
+    "channel",
+    "talk",
+    "dialogue_select",
+    "party_invite",
+    "party_accept",
+    "party_leave",
+    "party_kick",
+    "party_list",
+    "matchmake",
+    "companion",
+    "companion_status",
+    "companion_memory",
+    "companion_memory_write",
+    "character_info",
+    "abilities",
+    "leaderboard",
+    "suggest_description",
+    "buy",
+    "sell",
+    "accept_quest",
+    "complete_quest",
+    "guild_create",
+    "guild_invite",
+    "guild_leave",
+    "guild_info",
+    "guild_deposit",
+];
+
+/// The mock's answer to one request.
+#[derive(Debug, Clone)]
+pub struct Reply {
+    body: Map<String, Value>,
+    events: Vec<Value>,
+}
+
+impl Reply {
+    /// Answers with a successful result.
+    pub fn ok(result: Value) -> Self {
+        let body = match result {
+            Value::Object(body) => body,
+            other => Map::from_iter([("result".to_owned(), other)]),
+        };
+        Self {
+            body,
+            events: Vec::new(),
+        }
+    }
+
+    /// Answers with a server error.
+    pub fn error(code: &str, message: &str) -> Self {
+        Self::ok(json!({ "error": { "code": code, "message": message } }))
+    }
+
+    /// Pushes `data` as an event right after the response.
+    pub fn with_event(mut self, data: Value) -> Self {
+        let event = Map::from_iter([
+            ("type".to_owned(), Value::from("event")),
+            ("data".to_owned(), data),
+        ]);
+        self.events.push(Value::Object(event));
+        self
+    }
+}
+
+type Handler = Arc<dyn Fn(&Value) -> Reply + Send + Sync>;
+
+/// Per-action handlers the mock answers requests with.
+///
+/// Actions without a handler get an `unknown_action` error, as from a
+/// server that predates them.
+#[derive(Clone, Default)]
+pub struct Script {
+    handlers: HashMap<String, Handler>,
+}
+
+impl std::fmt::Debug for Script {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Script")
+            .field("actions", &self.handlers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Script {
+    /// Answers `action` by calling `handler` with the request's params.
+    pub fn on(
+        mut self,
+        action: &str,
+        handler: impl Fn(&Value) -> Reply + Send + Sync + 'static,
+    ) -> Self {
+        self.handlers.insert(action.to_owned(), Arc::new(handler));
+        self
+    }
+
+    /// A two-room world that answers every action the built-in tools send.
+    ///
+    /// The Town Square lies south of the North Gate. Any other action is
+    /// acknowledged with a message naming it.
+    pub fn world() -> Self {
+        let mut script = Self::default()
+            .on("connect", |params| {
+                let mut body = square();
+                if params["token"].as_str().unwrap_or_default().is_empty() {
+                    body["new_account"] = true.into();
+                    body["token"] = "mock-token".into();
+                }
+                Reply::ok(body)
+            })
+            .on("look", |params| match params["target"].as_str() {
+                Some(target) => {
+                    Reply::ok(json!({ "target": target, "description": "Unremarkable." }))
+                }
+                None => Reply::ok(square()),
+            })
+            .on("move", |params| match params["direction"].as_str() {
+                Some("north") => Reply::ok(gate()),
+                Some("south") => Reply::ok(square()),
+                _ => Reply::error("not_found", "You can't go that way."),
+            })
+            .on("attack", |params| {
+                let target = params["target"].as_str().unwrap_or("nobody");
+                Reply::ok(json!({ "message": format!("You hit {target}.") })).with_event(json!({
+                    "event": "combat_update",
+                    "attacker": target,
+                    "target": "you",
+                    "damage": 3,
+                    "hp": 17,
+                    "max_hp": 20
+                }))
+            })
+            .on("map", |_| {
+                Reply::ok(json!({ "map": "[North Gate]\n     |\n[Town Square]" }))
+            })
+            .on("status", |_| {
+                Reply::ok(json!({ "hp": 20, "max_hp": 20, "mana": 10, "level": 1 }))
+            })
+            .on("inventory", |_| {
+                Reply::ok(json!({ "items": ["rusty sword"], "gold": 12 }))
+            })
+            .on("rotate_token", |_| {
+                Reply::ok(json!({ "token": "mock-token-2" }))
+            })
+            .on("quests", |_| {
+                Reply::ok(json!({ "active": [], "completed": [] }))
+            });
+        for &action in ACKNOWLEDGED_ACTIONS {
+            script = script.on(action, move |_| {
+                Reply::ok(json!({ "message": format!("{action} done") }))
+            });
+        }
+        script
+    }
+
+    fn reply(&self, action: &str, params: &Value) -> Reply {
+        match self.handlers.get(action) {
+            Some(handler) => handler(params),
+            None => Reply::error("unknown_action", &format!("unknown action `{action}`")),
+        }
+    }
+}
+
+fn square() -> Value {
+    json!({ "room": { "id": "square", "name": "Town Square", "exits": { "north": "gate" } } })
+}
+
+fn gate() -> Value {
+    json!({ "room": { "id": "gate", "name": "North Gate", "exits": { "south": "square" } } })
+}
+
+/// A running mock game server.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+impl MockServer {
+    /// Starts serving `script` on `addr`; port 0 picks a free port.
+    ///
+    /// The server runs until the process exits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `addr` cannot be bound.
+    pub async fn start(addr: SocketAddr, script: Script) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let server = Self {
+            addr: listener.local_addr()?,
+            requests: Arc::default(),
+        };
+
+        let session = Session {
+            script: Arc::new(script),
+            requests: Arc::clone(&server.requests),
+        };
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(session.clone().run(stream));
+            }
+        });
+        tracing::info!(mock.addr = %server.addr, "mock.listening");
+        Ok(server)
+    }
+
+    /// Returns the WebSocket URL to connect to.
+    pub fn url(&self) -> String {
+        format!("ws://{}/ws", self.addr)
+    }
+
+    /// Returns every request received so far as `(action, params)`.
+    #[cfg(test)]
+    pub fn requests(&self) -> Vec<(String, Value)> {
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// What each connection needs to answer requests.
+#[derive(Clone)]
+struct Session {
+    script: Arc<Script>,
+    requests: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+impl Session {
+    /// Answers requests on one connection until it closes.
+    async fn run(self, stream: TcpStream) {
+        let mut ws = match tokio_tungstenite::accept_async(stream).await {
+            Ok(ws) => ws,
+            Err(err) => {
+                tracing::warn!(error = %err, "mock.handshake.failed");
+                return;
+            }
+        };
+        while let Some(Ok(msg)) = ws.next().await {
+            let text = match msg {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            for message in self.answer(&text) {
+                if ws
+                    .send(Message::Text(message.to_string().into()))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Returns the response to a request followed by its events.
+    fn answer(&self, text: &str) -> Vec<Value> {
+        let Ok(request) = serde_json::from_str::<Value>(text) else {
+            tracing::warn!("mock.request.invalid_json");
+            return Vec::new();
+        };
+        let action = request["action"].as_str().unwrap_or_default();
+        let params = request.get("params").cloned().unwrap_or_else(|| json!({}));
+        tracing::info!(msg.action = action, "mock.request");
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((action.to_owned(), params.clone()));
+
+        let reply = self.script.reply(action, &params);
+        let mut response = reply.body;
+        response.insert("id".to_owned(), request["id"].clone());
+        let mut outgoing = vec![Value::Object(response)];
+        outgoing.extend(reply.events);
+        outgoing
+    }
+}
@@ -0,0 +1,180 @@
+//! Immediate delivery of urgent push events to the MCP clients.
+//!
+//! Buffered events only reach Claude with the next tool response, which
+//! is too late when the player is attacked while idle. When enabled, a
+//! forwarder task watches the connection's live event feed and sends
+//! each urgent event as an MCP logging notification as soon as it
+//! arrives, to every attached client (see [`Peers`]). The event is
+//! still buffered for the next tool response.
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::{Peer, RoleServer};
+use tokio::sync::broadcast;
+
+use crate::protocol::PushEvent;
+use crate::resources::Subscriptions;
+
+/// Event types pushed immediately unless configured otherwise.
+pub const DEFAULT_URGENT_EVENTS: &[&str] = &["combat_update", "tell", "party_invite", "death"];
+
+/// Logger name attached to pushed event notifications.
+const EVENT_LOGGER: &str = "wyvern.events";
+
+/// The set of event types that warrant an immediate notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrgentEvents {
+    kinds: BTreeSet<String>,
+}
+
+impl UrgentEvents {
+    /// Builds the set from event type names such as `tell` or `death`.
+    ///
+    /// Names are matched against [`PushEvent::kind`], so server event
+    /// types this client does not model yet can be listed too.
+    pub fn new<I, S>(kinds: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            kinds: kinds
+                .into_iter()
+                .map(|kind| kind.as_ref().trim().to_owned())
+                .filter(|kind| !kind.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Returns true if `event` should be pushed immediately.
+    pub fn contains(&self, event: &PushEvent) -> bool {
+        self.kinds.contains(event.kind())
+    }
+}
+
+impl Default for UrgentEvents {
+    fn default() -> Self {
+        Self::new(DEFAULT_URGENT_EVENTS)
+    }
+}
+
+/// An attached MCP client and the resources it subscribed to.
+type Attached = (Peer<RoleServer>, Subscriptions);
+
+/// The MCP clients attached to this server, with the resources each
+/// subscribed to.
+///
+/// Over stdio there is one; over HTTP every client that finishes
+/// initializing is added, and dropped again once its transport closes.
+#[derive(Debug, Clone, Default)]
+pub struct Peers {
+    inner: Arc<Mutex<Vec<Attached>>>,
+}
+
+impl Peers {
+    /// Adds a newly initialized client and its resource subscriptions.
+    pub fn add(&self, peer: Peer<RoleServer>, subscriptions: Subscriptions) {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((peer, subscriptions));
+    }
+
+    /// Returns the clients still attached.
+    pub fn live(&self) -> Vec<Peer<RoleServer>> {
+        self.live_with_subscriptions()
+            .into_iter()
+            .map(|(peer, _)| peer)
+            .collect()
+    }
+
+    /// Returns the clients still attached with their subscriptions.
+    pub fn live_with_subscriptions(&self) -> Vec<Attached> {
+        let mut peers = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        peers.retain(|(peer, _)| !peer.is_transport_closed());
+        peers.clone()
+    }
+}
+
+/// Forwards urgent events from `feed` to every client in `peers` until
+/// the feed closes.
+///
+/// Each notification names the character `session` the event is for.
+pub async fn forward_urgent_events(
+    mut feed: broadcast::Receiver<PushEvent>,
+    peers: Peers,
+    urgent: UrgentEvents,
+    session: String,
+) {
+    loop {
+        let event = match feed.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(skipped, "mcp.notify.events.lagged");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if !urgent.contains(&event) {
+            continue;
+        }
+
+        let mut data = event.to_message();
+        if let Some(message) = data.as_object_mut() {
+            message.insert("session".to_owned(), session.clone().into());
+        }
+        let notification = LoggingMessageNotificationParam {
+            // Urgent events call for a prompt reaction; `alert` gets past
+            // any threshold a client is likely to set.
+            level: LoggingLevel::Alert,
+            logger: Some(EVENT_LOGGER.to_owned()),
+            data,
+        };
+        for peer in peers.live() {
+            if let Err(err) = peer.notify_logging_message(notification.clone()).await {
+                tracing::debug!(error = %err, "mcp.notify.events.failed");
+            }
+        }
+        tracing::debug!(event.kind = event.kind(), "mcp.notify.events.sent");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn default_set_covers_combat_tells_invites_and_death() {
+        let urgent = UrgentEvents::default();
+        let event = |value| PushEvent::from_message(value);
+
+        assert!(urgent.contains(&event(json!({
+            "type": "event",
+            "data": {"event": "tell", "from": "Bob", "message": "psst"}
+        }))));
+        assert!(urgent.contains(&event(json!({
+            "type": "event",
+            "data": {"event": "death", "killer": "Goblin"}
+        }))));
+        assert!(!urgent.contains(&event(json!({
+            "type": "event",
+            "data": {"event": "say", "from": "Bob", "message": "hi"}
+        }))));
+    }
+
+    #[test]
+    fn custom_set_matches_unmodelled_event_types() {
+        let urgent = UrgentEvents::new(["say", " weather_change ", ""]);
+        let weather = PushEvent::from_message(json!({
+            "type": "event",
+            "data": {"event": "weather_change", "weather": "storm"}
+        }));
+
+        assert!(urgent.contains(&weather));
+        assert_eq!(urgent.kinds.len(), 2);
+    }
+}
@@ -0,0 +1,225 @@
+//! Read-only mirror of the MCP session for local spectators.
+//!
+//! With `--observe <ADDR>`, a WebSocket endpoint on a loopback address
+//! streams every command the tools send, the server's answer to it and
+//! every push event, one JSON object per message tagged with the
+//! character session it belongs to:
+//!
+//! ```text
+//! {"ts":"2026-10-16T12:30:05.123Z","session":"aria","type":"command","action":"look","params":{}}
+//! {"ts":"2026-10-16T12:30:05.180Z","session":"aria","type":"response","action":"look","body":{...}}
+//! {"ts":"2026-10-16T12:30:06.002Z","session":"aria","type":"event","message":{"type":"event","data":{...}}}
+//! ```
+//!
+//! Observers cannot send commands and need no game login; anything they
+//! send is ignored. Tokens are redacted.
+
+use std::net::SocketAddr;
+use std::time::SystemTime;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::protocol::{PushEvent, ResponseBody};
+use crate::recorder::{format_timestamp, redact};
+
+/// Messages buffered per observer before a slow one starts missing some.
+const FEED_CAPACITY: usize = 256;
+
+/// Publishes the session to every attached observer.
+#[derive(Debug, Clone)]
+pub struct Observer {
+    feed: broadcast::Sender<String>,
+    addr: SocketAddr,
+}
+
+impl Observer {
+    /// Starts accepting observers on `addr`; port 0 picks a free port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `addr` is not a loopback address or cannot be
+    /// bound.
+    pub async fn bind(addr: SocketAddr) -> std::io::Result<Self> {
+        if !addr.ip().is_loopback() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("observer address {addr} is not a loopback address"),
+            ));
+        }
+        let listener = TcpListener::bind(addr).await?;
+        let observer = Self {
+            feed: broadcast::Sender::new(FEED_CAPACITY),
+            addr: listener.local_addr()?,
+        };
+
+        let feed = observer.feed.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, peer)) = listener.accept().await {
+                tokio::spawn(serve(stream, peer, feed.subscribe()));
+            }
+        });
+        tracing::info!(observer.url = %observer.url(), "observer.listening");
+        Ok(observer)
+    }
+
+    /// Returns the WebSocket URL observers connect to.
+    pub fn url(&self) -> String {
+        format!("ws://{}/", self.addr)
+    }
+
+    /// Mirrors a command about to be sent to the server.
+    pub fn command(&self, session: &str, action: &str, params: &Value) {
+        self.publish(
+            session,
+            json!({ "type": "command", "action": action, "params": params }),
+        );
+    }
+
+    /// Mirrors the server's answer to `action`.
+    pub fn response(&self, session: &str, action: &str, body: &ResponseBody) {
+        self.publish(
+            session,
+            json!({ "type": "response", "action": action, "body": body }),
+        );
+    }
+
+    /// Mirrors a command that got no answer from the server.
+    pub fn failure(&self, session: &str, action: &str, error: &str) {
+        self.publish(
+            session,
+            json!({ "type": "failure", "action": action, "error": error }),
+        );
+    }
+
+    /// Mirrors a push event.
+    pub fn event(&self, session: &str, event: &PushEvent) {
+        self.publish(
+            session,
+            json!({ "type": "event", "message": event.to_message() }),
+        );
+    }
+
+    fn publish(&self, session: &str, mut message: Value) {
+        // Nobody watching is the normal case; skip the encoding work.
+        if self.feed.receiver_count() == 0 {
+            return;
+        }
+        redact(&mut message);
+        message["ts"] = format_timestamp(SystemTime::now(), ':').into();
+        message["session"] = session.into();
+        let _ = self.feed.send(message.to_string());
+    }
+}
+
+/// Mirrors push events of `session` from `feed` to the observers until it
+/// closes.
+pub async fn mirror_events(
+    mut feed: broadcast::Receiver<PushEvent>,
+    observer: Observer,
+    session: String,
+) {
+    loop {
+        match feed.recv().await {
+            Ok(event) => observer.event(&session, &event),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(skipped, "observer.events.lagged");
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Streams the feed to one observer until either side closes.
+async fn serve(stream: TcpStream, peer: SocketAddr, mut feed: broadcast::Receiver<String>) {
+    let ws = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(err) => {
+            tracing::warn!(error = %err, observer.peer = %peer, "observer.handshake.failed");
+            return;
+        }
+    };
+    tracing::info!(observer.peer = %peer, "observer.attached");
+    let (mut write, mut read) = ws.split();
+    loop {
+        let text = tokio::select! {
+            message = feed.recv() => match message {
+                Ok(text) => text,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    json!({ "type": "lagged", "skipped": skipped }).to_string()
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            // Anything an observer sends is ignored; only a close matters.
+            incoming = read.next() => match incoming {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        if write.send(Message::Text(text.into())).await.is_err() {
+            break;
+        }
+    }
+    tracing::info!(observer.peer = %peer, "observer.detached");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn observers_see_redacted_commands_responses_and_events() {
+        assert!(Observer::bind(([192, 0, 2, 1], 0).into()).await.is_err());
+
+        let observer = Observer::bind(([127, 0, 0, 1], 0).into())
+            .await
+            .expect("observer");
+        let (mut ws, _) = tokio_tungstenite::connect_async(observer.url())
+            .await
+            .expect("attached");
+        // Wait for the server side to subscribe before publishing.
+        while observer.feed.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        observer.command(
+            "aria",
+            "connect",
+            &json!({ "username": "aria", "token": "s3cret" }),
+        );
+        observer.response(
+            "aria",
+            "connect",
+            &ResponseBody::Success(serde_json::Map::from_iter([(
+                "room".to_owned(),
+                json!("square"),
+            )])),
+        );
+        observer.event(
+            "aria",
+            &PushEvent::from_message(json!({
+            "type": "event",
+            "data": { "event": "tell", "from": "brom", "message": "hi" }
+            })),
+        );
+
+        let mut received = Vec::new();
+        while received.len() < 3 {
+            match ws.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    received.push(serde_json::from_str::<Value>(&text).expect("json"));
+                }
+                other => panic!("unexpected message {other:?}"),
+            }
+        }
+        assert_eq!(received[0]["type"], "command");
+        assert_eq!(received[0]["params"]["token"], "[redacted]");
+        assert_eq!(received[1]["body"]["room"], "square");
+        assert_eq!(received[2]["message"]["data"]["event"], "tell");
+        assert!(received.iter().all(|message| message["ts"].is_string()));
+        assert!(received.iter().all(|message| message["session"] == "aria"));
+    }
+}
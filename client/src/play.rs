@@ -0,0 +1,468 @@
+//! `wyvern play`: a terminal REPL for playing without an MCP client.
+//!
+//! Reads MUD-style commands ("n", "attack goblin", "tell brom hi there"),
+//! sends them over a [`GameConnection`] and prints the responses. Push
+//! events are printed above the prompt as they arrive. Line editing,
+//! history and tab completion of command names come from `rustyline`.
+
+use std::path::PathBuf;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, ExternalPrinter, Helper};
+use serde_json::{Map, Value};
+use tokio::runtime::Handle;
+
+use crate::connection::{ConnectionOptions, GameConnection};
+use crate::events::EventBuffer;
+use crate::protocol::{ConnectResult, PushEvent, ResponseBody, RoomView};
+use crate::tokens::TokenStore;
+
+/// A command word and the server action it sends.
+///
+/// Words after the command fill `params` in order; the last parameter
+/// takes the rest of the line.
+#[derive(Debug)]
+struct Verb {
+    name: &'static str,
+    action: &'static str,
+    params: &'static [&'static str],
+}
+
+impl Verb {
+    const fn new(
+        name: &'static str,
+        action: &'static str,
+        params: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            action,
+            params,
+        }
+    }
+}
+
+/// Commands understood by the REPL, named after the matching MCP tools.
+const VERBS: &[Verb] = &[
+    Verb::new("look", "look", &["target"]),
+    Verb::new("move", "move", &["direction"]),
+    Verb::new("go", "move", &["direction"]),
+    Verb::new("map", "map", &[]),
+    Verb::new("attack", "attack", &["target"]),
+    Verb::new("use_ability", "use_ability", &["ability", "target"]),
+    Verb::new("flee", "flee", &[]),
+    Verb::new("status", "status", &[]),
+    Verb::new("inventory", "inventory", &[]),
+    Verb::new("get", "get", &["item"]),
+    Verb::new("drop", "drop", &["item"]),
+    Verb::new("equip", "equip", &["item"]),
+    Verb::new("use_item", "use_item", &["item"]),
+    Verb::new("say", "say", &["message"]),
+    Verb::new("tell", "tell", &["player", "message"]),
+    Verb::new("shout", "shout", &["message"]),
+    Verb::new("emote", "emote", &["action"]),
+    Verb::new("who", "who", &[]),
+    Verb::new("channel", "channel", &["name", "message"]),
+    Verb::new("talk", "talk", &["target"]),
+    Verb::new("dialogue_select", "dialogue_select", &["npc", "option"]),
+    Verb::new("party_invite", "party_invite", &["player"]),
+    Verb::new("party_accept", "party_accept", &[]),
+    Verb::new("party_leave", "party_leave", &[]),
+    Verb::new("party_kick", "party_kick", &["player"]),
+    Verb::new("party_list", "party_list", &[]),
+    Verb::new("companion", "companion", &["command"]),
+    Verb::new("companion_status", "companion_status", &[]),
+    Verb::new("character_info", "character_info", &[]),
+    Verb::new("abilities", "abilities", &[]),
+    Verb::new("quests", "quests", &[]),
+    Verb::new("leaderboard", "leaderboard", &["board_type"]),
+    Verb::new("buy", "buy", &["shop_id", "item"]),
+    Verb::new("sell", "sell", &["shop_id", "item"]),
+    Verb::new("accept_quest", "accept_quest", &["quest_id"]),
+    Verb::new("complete_quest", "complete_quest", &["quest_id"]),
+    Verb::new("guild_create", "guild_create", &["name"]),
+    Verb::new("guild_invite", "guild_invite", &["player"]),
+    Verb::new("guild_leave", "guild_leave", &[]),
+    Verb::new("guild_info", "guild_info", &[]),
+    Verb::new("guild_deposit", "guild_deposit", &["amount"]),
+];
+
+/// Parameters the server expects as numbers rather than strings.
+const NUMERIC_PARAMS: &[&str] = &["option", "amount"];
+
+/// One-word shortcuts, expanded before parsing.
+const SHORTCUTS: &[(&str, &str)] = &[
+    ("n", "move north"),
+    ("s", "move south"),
+    ("e", "move east"),
+    ("w", "move west"),
+    ("ne", "move northeast"),
+    ("nw", "move northwest"),
+    ("se", "move southeast"),
+    ("sw", "move southwest"),
+    ("u", "move up"),
+    ("d", "move down"),
+    ("l", "look"),
+    ("i", "inventory"),
+    ("inv", "inventory"),
+];
+
+/// Shown when the login created a new character.
+pub const NEW_ACCOUNT_NOTE: &str = "Created a new character; its token is saved.";
+
+/// Commands handled by the REPL itself.
+const LOCAL_COMMANDS: &[&str] = &["help", "quit"];
+
+/// What a line of input asks for.
+#[derive(Debug, PartialEq)]
+pub enum Input {
+    /// Send `action` to the server.
+    Send { action: &'static str, params: Value },
+    /// List the available commands.
+    Help,
+    /// Leave the REPL.
+    Quit,
+    /// Nothing to do.
+    Empty,
+}
+
+/// Parses one line of input.
+///
+/// # Errors
+///
+/// Returns a message for the player if the command is unknown.
+pub fn parse(line: &str) -> Result<Input, String> {
+    let line = line.trim();
+    let line = SHORTCUTS
+        .iter()
+        .find(|(shortcut, _)| *shortcut == line)
+        .map_or(line, |(_, expanded)| expanded);
+    let (word, mut rest) = line.split_once(' ').unwrap_or((line, ""));
+    match word {
+        "" => return Ok(Input::Empty),
+        "help" | "?" => return Ok(Input::Help),
+        "quit" | "exit" => return Ok(Input::Quit),
+        _ => {}
+    }
+    let verb = VERBS
+        .iter()
+        .find(|verb| verb.name == word)
+        .ok_or_else(|| format!("unknown command `{word}`; type `help` for a list"))?;
+
+    let mut params = Map::new();
+    for (i, &key) in verb.params.iter().enumerate() {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let value = if i + 1 == verb.params.len() {
+            std::mem::take(&mut rest).trim_end()
+        } else {
+            let (value, tail) = rest.split_once(' ').unwrap_or((rest, ""));
+            rest = tail;
+            value
+        };
+        let value = match value.parse::<u64>() {
+            Ok(number) if NUMERIC_PARAMS.contains(&key) => Value::from(number),
+            _ => Value::from(value),
+        };
+        params.insert(key.to_owned(), value);
+    }
+    Ok(Input::Send {
+        action: verb.action,
+        params: Value::Object(params),
+    })
+}
+
+/// Completes command names at the start of the line.
+#[derive(Debug)]
+struct CommandCompleter;
+
+impl Completer for CommandCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let prefix = &line[..pos];
+        if prefix.contains(' ') {
+            return Ok((pos, Vec::new()));
+        }
+        let names = VERBS
+            .iter()
+            .map(|verb| verb.name)
+            .chain(LOCAL_COMMANDS.iter().copied());
+        let candidates = names
+            .filter(|name| name.starts_with(prefix))
+            .map(|name| Pair {
+                display: name.to_owned(),
+                replacement: format!("{name} "),
+            })
+            .collect();
+        Ok((0, candidates))
+    }
+}
+
+impl Hinter for CommandCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for CommandCompleter {}
+
+impl Validator for CommandCompleter {}
+
+impl Helper for CommandCompleter {}
+
+/// Where and as whom to play.
+#[derive(Debug)]
+pub struct PlayOptions {
+    /// WebSocket URL of the game server.
+    pub server: String,
+    /// Character to log in as.
+    pub username: String,
+    /// Where the character's token is saved.
+    pub tokens: TokenStore,
+    /// Connection settings.
+    pub connection: ConnectionOptions,
+}
+
+/// A logged-in character, as returned by [`login`].
+#[derive(Debug)]
+pub struct Session {
+    /// Connection to the game server.
+    pub connection: GameConnection,
+    /// Push events received since login.
+    pub events: EventBuffer,
+    /// The server's answer to `connect`, usually the starting room.
+    pub welcome: Map<String, Value>,
+    /// True if the server created a new character for this login.
+    pub new_account: bool,
+}
+
+/// Connects and logs in, saving the token of a new account.
+///
+/// # Errors
+///
+/// Returns an error if the server cannot be reached or refuses the login.
+pub async fn login(options: PlayOptions) -> anyhow::Result<Session> {
+    let (event_tx, event_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut connection = GameConnection::new(event_tx, options.connection);
+    connection.connect(&options.server).await?;
+
+    let username = options.username;
+    let token = options.tokens.read(&username)?.unwrap_or_default();
+    let params = serde_json::json!({ "username": username, "token": token });
+    let body = connection.send_command("connect", params).await?.body;
+    let welcome = match body {
+        ResponseBody::Success(result) => result,
+        ResponseBody::Error { error } => {
+            anyhow::bail!("login refused ({:?}): {}", error.code, error.message)
+        }
+    };
+
+    let mut token = token;
+    let connect = ResponseBody::Success(welcome.clone())
+        .parse::<ConnectResult>()
+        .unwrap_or_default();
+    let new_account = connect.new_account;
+    if let Some(new_token) = connect.token.filter(|_| new_account) {
+        options.tokens.write(&username, &new_token)?;
+        token = new_token;
+    }
+    connection.set_credentials(username, token).await;
+    Ok(Session {
+        connection,
+        events: EventBuffer::new(event_rx),
+        welcome,
+        new_account,
+    })
+}
+
+/// Logs in and runs the REPL until the player quits, keeping the command
+/// history in `history`.
+///
+/// # Errors
+///
+/// Returns an error if the server cannot be reached, the login is
+/// refused, or the terminal cannot be used.
+pub async fn run(options: PlayOptions, history: PathBuf) -> anyhow::Result<()> {
+    let Session {
+        connection,
+        events,
+        welcome,
+        new_account,
+    } = login(options).await?;
+    if new_account {
+        println!("{NEW_ACCOUNT_NOTE}");
+    }
+    println!("{}", render_result(&welcome));
+
+    let mut editor = Editor::<CommandCompleter, DefaultHistory>::new()?;
+    editor.set_helper(Some(CommandCompleter));
+    let _ = editor.load_history(&history);
+
+    // Without a terminal (e.g. piped input) events are printed plainly.
+    let printer = editor.create_external_printer().ok();
+    let printing = tokio::spawn(print_events(events, printer));
+
+    // The editor blocks on the terminal, so it runs on its own thread and
+    // drives each command back on the runtime.
+    let handle = Handle::current();
+    let mut connection = tokio::task::spawn_blocking(move || {
+        repl(&mut editor, &connection, &handle);
+        if let Err(err) = editor.save_history(&history) {
+            tracing::warn!(error = %err, path = %history.display(), "play.history.save.failed");
+        }
+        connection
+    })
+    .await?;
+    printing.abort();
+    connection.disconnect().await;
+    Ok(())
+}
+
+/// Prints push events as they arrive, above the prompt when there is one.
+async fn print_events(mut events: EventBuffer, mut printer: Option<impl ExternalPrinter>) {
+    while let Some(event) = events.recv().await {
+        let line = render_event(&event);
+        match &mut printer {
+            Some(printer) => {
+                if printer.print(line).is_err() {
+                    return;
+                }
+            }
+            None => println!("{line}"),
+        }
+    }
+}
+
+/// Reads and runs commands until the player quits or closes the input.
+fn repl(
+    editor: &mut Editor<CommandCompleter, DefaultHistory>,
+    connection: &GameConnection,
+    handle: &Handle,
+) {
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => return,
+            Err(err) => {
+                eprintln!("input error: {err}");
+                return;
+            }
+        };
+        let _ = editor.add_history_entry(line.as_str());
+
+        match parse(&line) {
+            Ok(Input::Send { action, params }) => {
+                match handle.block_on(connection.send_command(action, params)) {
+                    Ok(response) => match response.body {
+                        ResponseBody::Success(result) => println!("{}", render_result(&result)),
+                        ResponseBody::Error { error } => println!("error: {}", error.message),
+                    },
+                    Err(err) => println!("error: {err}"),
+                }
+            }
+            Ok(Input::Help) => println!("{}", help()),
+            Ok(Input::Quit) => return,
+            Ok(Input::Empty) => {}
+            Err(message) => println!("{message}"),
+        }
+    }
+}
+
+/// Lists the commands and shortcuts.
+pub fn help() -> String {
+    use std::fmt::Write as _;
+    let mut text = String::from("Commands:\n");
+    for verb in VERBS {
+        let args: Vec<_> = verb
+            .params
+            .iter()
+            .map(|param| format!("<{param}>"))
+            .collect();
+        let _ = writeln!(text, "  {} {}", verb.name, args.join(" "));
+    }
+    let shortcuts: Vec<_> = SHORTCUTS.iter().map(|(shortcut, _)| *shortcut).collect();
+    let _ = writeln!(text, "Shortcuts: {}", shortcuts.join(" "));
+    text.push_str("Type `quit` or press Ctrl-D to leave.");
+    text
+}
+
+/// Formats a success payload: its message or the room, else the raw JSON.
+pub fn render_result(result: &Map<String, Value>) -> String {
+    let mut lines = Vec::new();
+    if let Some(room) = RoomView::from_result(result) {
+        lines.push(format!(
+            "== {} ==",
+            room.name.as_deref().unwrap_or(&room.id)
+        ));
+        let exits: Vec<_> = room.exits.keys().map(String::as_str).collect();
+        lines.push(format!("Exits: {}", exits.join(", ")));
+    }
+    if let Some(message) = result.get("message").and_then(Value::as_str) {
+        lines.push(message.to_owned());
+    }
+    if lines.is_empty() {
+        lines.push(serde_json::to_string_pretty(result).unwrap_or_default());
+    }
+    lines.join("\n")
+}
+
+/// Formats a push event as one line.
+pub fn render_event(event: &PushEvent) -> String {
+    let message = event.to_message();
+    let data = message.get("data").unwrap_or(&message);
+    match data.get("message").and_then(Value::as_str) {
+        Some(text) => format!("[{}] {text}", event.kind()),
+        None => format!("[{}] {data}", event.kind()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn lines_parse_into_actions() {
+        let send = |line| match parse(line) {
+            Ok(Input::Send { action, params }) => (action, params),
+            other => panic!("`{line}` parsed as {other:?}"),
+        };
+        assert_eq!(send("n"), ("move", json!({ "direction": "north" })));
+        assert_eq!(
+            send("attack goblin"),
+            ("attack", json!({ "target": "goblin" }))
+        );
+        assert_eq!(
+            send("say hi  there "),
+            ("say", json!({ "message": "hi  there" }))
+        );
+        assert_eq!(
+            send("tell brom meet me at the gate"),
+            (
+                "tell",
+                json!({ "player": "brom", "message": "meet me at the gate" })
+            )
+        );
+        assert_eq!(
+            send("guild_deposit 50"),
+            ("guild_deposit", json!({ "amount": 50 }))
+        );
+        assert_eq!(send("look"), ("look", json!({})));
+
+        assert_eq!(parse("  "), Ok(Input::Empty));
+        assert_eq!(parse("quit"), Ok(Input::Quit));
+        assert!(parse("dance wildly").is_err());
+    }
+}
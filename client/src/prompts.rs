@@ -0,0 +1,233 @@
+//! Built-in MCP prompts for common play patterns.
+//!
+//! The prompt text ships with the binary, so it always matches the tool
+//! set this client exposes. Tool names are written in backticks and
+//! checked against the tool router in the tests below.
+
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::{PromptMessage, PromptMessageRole};
+use rmcp::{prompt, prompt_router};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::tools::GameHandler;
+
+// ---------------------------------------------------------------------------
+// Argument types
+// ---------------------------------------------------------------------------
+
+/// Arguments for the `start_session` prompt.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StartSessionArgs {
+    /// Character to log in as. Asks the player if omitted.
+    pub username: Option<String>,
+}
+
+/// Arguments for the `narrate_combat` prompt.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NarrateCombatArgs {
+    /// Narration style, e.g. "grim", "heroic" or "comedic".
+    pub style: Option<String>,
+}
+
+/// Arguments for the `shop_assistant` prompt.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ShopAssistantArgs {
+    /// What the player is shopping for, e.g. "better armor" or "healing potions".
+    pub goal: Option<String>,
+}
+
+/// Arguments for the `quest_planner` prompt.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct QuestPlannerArgs {
+    /// A specific quest to plan around. Plans the whole log if omitted.
+    pub quest: Option<String>,
+}
+
+/// Arguments for the `companion_roleplay` prompt.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CompanionRoleplayArgs {
+    /// Personality to play the companion with, e.g. "gruff but loyal".
+    pub personality: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Prompts
+// ---------------------------------------------------------------------------
+
+#[prompt_router(vis = "pub(crate)")]
+impl GameHandler {
+    /// Log in, get your bearings and suggest what to do next.
+    #[prompt(name = "start_session")]
+    async fn start_session(
+        &self,
+        Parameters(args): Parameters<StartSessionArgs>,
+    ) -> Vec<PromptMessage> {
+        let login = match args.username {
+            Some(username) => format!(
+                "Call `connect` with username \"{username}\" and leave the token argument \
+                 out so the saved token is used."
+            ),
+            None => "Ask me which character to play, then call `connect` with that \
+                     username and leave the token argument out so the saved token is used."
+                .to_owned(),
+        };
+        user_message(format!(
+            "Let's play Weights & Wyverns. {login} If the response says new_account, \
+             show me the new token once and tell me it has been saved.\n\n\
+             Then get your bearings: `look` at the room, check `status` and `quests`, \
+             and glance at the `map`. Summarise where I am, how I'm doing and anything \
+             urgent in the events, and suggest two or three things to do next. Narrate \
+             in second person and keep mechanics light unless I ask for numbers."
+        ))
+    }
+
+    /// Fight a battle turn by turn with vivid narration.
+    #[prompt(name = "narrate_combat")]
+    async fn narrate_combat(
+        &self,
+        Parameters(args): Parameters<NarrateCombatArgs>,
+    ) -> Vec<PromptMessage> {
+        let style = args.style.unwrap_or_else(|| "cinematic".to_owned());
+        user_message(format!(
+            "Run this fight with me in a {style} style.\n\n\
+             Before the first blow, check `status` and `abilities` so you know my HP, \
+             mana and cooldowns. Each turn, ask what I want to do, or suggest a move, \
+             then use `attack`, `use_ability` or `use_item` for potions. Turn every \
+             result and every combat_update event into a short, vivid beat of \
+             narration, and mention HP when it changes meaningfully.\n\n\
+             If my HP drops below a quarter and I have no healing, warn me and offer \
+             to `flee`. When the fight ends, summarise loot and XP, then `look` around."
+        ))
+    }
+
+    /// Compare shop stock against your gear and budget.
+    #[prompt(name = "shop_assistant")]
+    async fn shop_assistant(
+        &self,
+        Parameters(args): Parameters<ShopAssistantArgs>,
+    ) -> Vec<PromptMessage> {
+        let goal = args
+            .goal
+            .map(|goal| format!(" I'm looking for {goal}."))
+            .unwrap_or_default();
+        user_message(format!(
+            "Help me shop.{goal}\n\n\
+             Check my gold with `status`, my gear with `inventory` and \
+             `character_info`, then `talk` to the shopkeeper here to see the wares. \
+             Recommend what to buy and what junk to sell, with a one-line reason for \
+             each, and keep enough gold for healing supplies.\n\n\
+             Only call `buy` or `sell` after I confirm. Offer to `equip` anything \
+             that is an upgrade."
+        ))
+    }
+
+    /// Plan a route through your quest log.
+    #[prompt(name = "quest_planner")]
+    async fn quest_planner(
+        &self,
+        Parameters(args): Parameters<QuestPlannerArgs>,
+    ) -> Vec<PromptMessage> {
+        let focus = match args.quest {
+            Some(quest) => format!("Focus on the quest \"{quest}\"."),
+            None => "Consider every active quest.".to_owned(),
+        };
+        user_message(format!(
+            "Plan my questing. {focus}\n\n\
+             Read the log with `quests`, check my level with `status`, and use the \
+             `map` to see which objectives are close. Propose an order that avoids \
+             backtracking and flags anything too dangerous for my level.\n\n\
+             As we go, use `travel_to` to reach rooms I've already explored and \
+             `move_direction` into new ground, `talk` and `dialogue_select` \
+             for quest givers, `accept_quest` for new quests along the way, and \
+             `complete_quest` as soon as objectives are done."
+        ))
+    }
+
+    /// Give your AI companion a voice and a memory.
+    #[prompt(name = "companion_roleplay")]
+    async fn companion_roleplay(
+        &self,
+        Parameters(args): Parameters<CompanionRoleplayArgs>,
+    ) -> Vec<PromptMessage> {
+        let personality = args
+            .personality
+            .map(|personality| format!(" Play them as {personality}."))
+            .unwrap_or_default();
+        user_message(format!(
+            "Voice my companion as a character in their own right.{personality}\n\n\
+             Start by reading `companion_status` and `companion_memory` so they \
+             remember our journey. Let them comment on what happens in their own \
+             voice, and speak or act in the world with `say` and `emote` when it \
+             fits. When I give them orders, pass them on with `companion`.\n\n\
+             After anything memorable — a victory, a near death, a new friend — \
+             record it with `companion_memory_write` so it survives to the next \
+             session."
+        ))
+    }
+}
+
+/// Wraps prompt text as a single user message.
+fn user_message(text: String) -> Vec<PromptMessage> {
+    vec![PromptMessage::new_text(PromptMessageRole::User, text)]
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::PromptMessageContent;
+
+    use super::*;
+    use crate::tools::HandlerOptions;
+
+    /// Returns every backticked word in the prompt messages.
+    fn tool_mentions(messages: &[PromptMessage]) -> Vec<String> {
+        messages
+            .iter()
+            .filter_map(|message| match &message.content {
+                PromptMessageContent::Text { text } => Some(text),
+                _ => None,
+            })
+            .flat_map(|text| text.split('`').skip(1).step_by(2))
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn prompts_only_mention_exposed_tools() {
+        let handler = GameHandler::new(
+            "ws://localhost:8080/ws".into(),
+            "/tmp".into(),
+            HandlerOptions::default(),
+        );
+        assert_eq!(GameHandler::prompt_router().list_all().len(), 5);
+
+        let prompts = [
+            handler
+                .start_session(Parameters(StartSessionArgs { username: None }))
+                .await,
+            handler
+                .narrate_combat(Parameters(NarrateCombatArgs { style: None }))
+                .await,
+            handler
+                .shop_assistant(Parameters(ShopAssistantArgs { goal: None }))
+                .await,
+            handler
+                .quest_planner(Parameters(QuestPlannerArgs { quest: None }))
+                .await,
+            handler
+                .companion_roleplay(Parameters(CompanionRoleplayArgs { personality: None }))
+                .await,
+        ];
+
+        for messages in &prompts {
+            let mentions = tool_mentions(messages);
+            assert!(!mentions.is_empty());
+            for name in mentions {
+                assert!(
+                    handler.has_tool(&name),
+                    "prompt mentions unknown tool `{name}`"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,526 @@
+//! Typed model of the game server wire protocol.
+//!
+//! Requests are `{id, action, params}` envelopes. The server answers each
+//! with a response carrying the same `id`, either a success body or an
+//! `error` object with a machine-readable code. Unsolicited push events
+//! arrive as `{"type": "event", "data": {"event": <kind>, ...}}`.
+//!
+//! Every type keeps unrecognised fields, and unknown event kinds fall
+//! back to raw JSON, so an older client keeps working against a newer
+//! server.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+// ---------------------------------------------------------------------------
+// Requests
+// ---------------------------------------------------------------------------
+
+/// A command sent to the game server.
+#[derive(Debug, Serialize)]
+pub struct Request<'a> {
+    /// Correlation ID echoed back in the response.
+    pub id: &'a str,
+    /// Server action name (e.g. `look`, `attack`).
+    pub action: &'a str,
+    /// Action-specific parameters.
+    pub params: &'a Value,
+}
+
+// ---------------------------------------------------------------------------
+// Responses
+// ---------------------------------------------------------------------------
+
+/// The server's answer to a [`Request`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    /// Correlation ID of the request being answered.
+    pub id: String,
+    /// Success payload or error details.
+    #[serde(flatten)]
+    pub body: ResponseBody,
+}
+
+/// Outcome carried by a [`Response`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ResponseBody {
+    /// The server rejected the command.
+    Error {
+        /// What went wrong.
+        error: ServerError,
+    },
+    /// The command succeeded; fields are action-specific.
+    Success(Map<String, Value>),
+}
+
+/// Error details returned by the server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerError {
+    /// Machine-readable error code.
+    pub code: ErrorCode,
+    /// Human-readable explanation.
+    #[serde(default)]
+    pub message: String,
+    /// Any additional fields the server attached.
+    #[serde(flatten)]
+    pub details: Map<String, Value>,
+}
+
+/// Machine-readable error codes sent by the server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The session has not authenticated yet.
+    NotAuthenticated,
+    /// The username/token pair was rejected.
+    InvalidToken,
+    /// The action name is not recognised by the server.
+    UnknownAction,
+    /// Parameters were missing or malformed.
+    InvalidParams,
+    /// The named target, item, or player does not exist here.
+    NotFound,
+    /// The action is on cooldown or the character is off balance.
+    Cooldown,
+    /// The character cannot afford the action.
+    InsufficientFunds,
+    /// Too many commands in a short period.
+    RateLimited,
+    /// The server hit an internal failure.
+    Internal,
+    /// A code this client does not know about yet.
+    #[serde(untagged)]
+    Other(String),
+}
+
+/// Success payload of the `connect` action.
+#[derive(Debug, Default, Deserialize)]
+pub struct ConnectResult {
+    /// True if the server created a fresh character for this login.
+    #[serde(default)]
+    pub new_account: bool,
+    /// Token issued for a new account, to be stored for future logins.
+    pub token: Option<String>,
+}
+
+/// Success payload of the `rotate_token` action.
+#[derive(Debug, Deserialize)]
+pub struct RotateTokenResult {
+    /// Replacement token; the previous one no longer authenticates.
+    pub token: String,
+}
+
+/// Success payload of the `tool_manifest` action.
+///
+/// Describes server actions as MCP tools so new features can be exposed
+/// without a client release.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ToolManifest {
+    /// Tools offered by the server.
+    pub tools: Vec<ManifestTool>,
+}
+
+/// A single server-described tool.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ManifestTool {
+    /// MCP tool name.
+    pub name: String,
+    /// Description shown to Claude.
+    #[serde(default)]
+    pub description: String,
+    /// JSON Schema for the tool arguments.
+    #[serde(default, alias = "inputSchema")]
+    pub input_schema: Map<String, Value>,
+    /// Server action to invoke; defaults to the tool name.
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+impl ResponseBody {
+    /// Decodes a success payload into a typed struct.
+    ///
+    /// Returns `None` for error responses or payloads that do not match `T`.
+    pub fn parse<T: serde::de::DeserializeOwned>(&self) -> Option<T> {
+        match self {
+            Self::Success(body) => serde_json::from_value(Value::Object(body.clone())).ok(),
+            Self::Error { .. } => None,
+        }
+    }
+}
+
+/// The room description carried by `look` and `move` responses.
+///
+/// Servers nest the room under a `room` key or send it at the top level,
+/// and list exits either as bare directions or with their destinations,
+/// so this is decoded leniently rather than derived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomView {
+    /// Stable room identifier, falling back to the room name.
+    pub id: String,
+    /// Display name of the room.
+    pub name: Option<String>,
+    /// Exit direction mapped to the destination room, where the server says.
+    pub exits: BTreeMap<String, Option<String>>,
+}
+
+impl RoomView {
+    /// Extracts the room from a success payload, if it describes one.
+    pub fn from_result(result: &Map<String, Value>) -> Option<Self> {
+        let room = match result.get("room") {
+            Some(Value::Object(room)) => room,
+            _ => result,
+        };
+        let text = |map: &Map<String, Value>, key: &str| {
+            map.get(key).and_then(Value::as_str).map(str::to_owned)
+        };
+
+        let name = text(room, "name").or_else(|| text(result, "room"));
+        let id = text(room, "id")
+            .or_else(|| text(room, "room_id"))
+            .or_else(|| text(result, "room_id"))
+            .or_else(|| name.clone())?;
+
+        let exits = match room.get("exits")? {
+            Value::Object(exits) => exits
+                .iter()
+                .map(|(direction, to)| (direction.clone(), exit_destination(to)))
+                .collect(),
+            Value::Array(exits) => exits
+                .iter()
+                .filter_map(|exit| match exit {
+                    Value::String(direction) => Some((direction.clone(), None)),
+                    Value::Object(exit) => {
+                        let direction = text(exit, "direction").or_else(|| text(exit, "dir"))?;
+                        Some((direction, exit_destination(&Value::Object(exit.clone()))))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => return None,
+        };
+
+        Some(Self { id, name, exits })
+    }
+}
+
+/// Reads the destination of an exit given as an ID or an exit object.
+fn exit_destination(exit: &Value) -> Option<String> {
+    match exit {
+        Value::String(to) => Some(to.clone()),
+        Value::Object(exit) => ["to", "room_id", "destination"]
+            .iter()
+            .find_map(|key| exit.get(*key).and_then(Value::as_str))
+            .map(str::to_owned),
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Push events
+// ---------------------------------------------------------------------------
+
+/// An unsolicited event pushed by the server, keyed by its `event` field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PushEvent {
+    /// Another player walked into the room.
+    PlayerEntered(PlayerMovement),
+    /// Another player left the room.
+    PlayerLeft(PlayerMovement),
+    /// A round of combat was resolved.
+    CombatUpdate(CombatUpdate),
+    /// Someone spoke aloud in the room.
+    Say(ChatMessage),
+    /// A private message arrived.
+    Tell(ChatMessage),
+    /// A zone-wide shout.
+    Shout(ChatMessage),
+    /// A message on a chat channel.
+    Channel(ChatMessage),
+    /// Another player invited this character to a party.
+    PartyInvite(PartyInvite),
+    /// This character died.
+    Death(Death),
+    /// This character gained a level.
+    LevelUp(LevelUp),
+    /// The client dropped events because too many accumulated.
+    EventsOverflow(Notice),
+    /// The client lost its link to the server.
+    ConnectionLost(Notice),
+    /// The client re-established its link to the server.
+    ConnectionRestored(Notice),
+    /// An event this client does not know about yet, kept as the whole
+    /// raw message.
+    #[serde(untagged)]
+    Unknown(Value),
+}
+
+impl PushEvent {
+    /// Decodes a raw server message into a push event.
+    ///
+    /// Messages that are not in the `{"type": "event", "data": ...}`
+    /// envelope, or whose data does not match a known kind, are kept
+    /// whole as [`PushEvent::Unknown`].
+    pub fn from_message(message: Value) -> Self {
+        let is_event = message.get("type").and_then(Value::as_str) == Some("event");
+        let known = match message.get("data") {
+            Some(data) if is_event => serde_json::from_value(data.clone()).ok(),
+            _ => None,
+        };
+        match known {
+            Some(Self::Unknown(_)) | None => Self::Unknown(message),
+            Some(event) => event,
+        }
+    }
+
+    /// Encodes the event back into the server's envelope format.
+    pub fn to_message(&self) -> Value {
+        match self {
+            Self::Unknown(raw) => raw.clone(),
+            event => serde_json::json!({ "type": "event", "data": event }),
+        }
+    }
+
+    /// Returns true for events that should halt automated play, such as
+    /// multi-step travel: combat, death and anything awaiting a reply.
+    pub fn is_interrupting(&self) -> bool {
+        matches!(
+            self,
+            Self::CombatUpdate(_) | Self::Death(_) | Self::Tell(_) | Self::PartyInvite(_)
+        )
+    }
+
+    /// Returns the wire name of this event kind (e.g. `player_entered`).
+    pub fn kind(&self) -> &str {
+        match self {
+            Self::PlayerEntered(_) => "player_entered",
+            Self::PlayerLeft(_) => "player_left",
+            Self::CombatUpdate(_) => "combat_update",
+            Self::Say(_) => "say",
+            Self::Tell(_) => "tell",
+            Self::Shout(_) => "shout",
+            Self::Channel(_) => "channel",
+            Self::PartyInvite(_) => "party_invite",
+            Self::Death(_) => "death",
+            Self::LevelUp(_) => "level_up",
+            Self::EventsOverflow(_) => "events_overflow",
+            Self::ConnectionLost(_) => "connection_lost",
+            Self::ConnectionRestored(_) => "connection_restored",
+            Self::Unknown(raw) => raw
+                .get("data")
+                .and_then(|data| data.get("event"))
+                .and_then(Value::as_str)
+                .unwrap_or("unknown"),
+        }
+    }
+}
+
+/// A player entering or leaving the room.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerMovement {
+    /// Name of the player who moved.
+    pub player: String,
+    /// Direction they came from or left towards.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<String>,
+    /// Any additional fields the server attached.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Result of a combat round.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CombatUpdate {
+    /// Who acted this round.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attacker: Option<String>,
+    /// Who was on the receiving end.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Damage dealt, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub damage: Option<i64>,
+    /// This character's remaining hit points.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hp: Option<i64>,
+    /// This character's maximum hit points.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_hp: Option<i64>,
+    /// Any additional fields the server attached.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A spoken, shouted, private, or channel message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// Name of the speaker.
+    pub from: String,
+    /// Message text.
+    pub message: String,
+    /// Channel name, for channel messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// Any additional fields the server attached.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An invitation to join a party.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartyInvite {
+    /// Player who sent the invite.
+    pub from: String,
+    /// Any additional fields the server attached.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Death of this character.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Death {
+    /// What dealt the killing blow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub killer: Option<String>,
+    /// Any additional fields the server attached.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A level gained by this character.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelUp {
+    /// The new level.
+    pub level: u32,
+    /// Any additional fields the server attached.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A human-readable notice, used for client-generated events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notice {
+    /// Text for Claude to narrate.
+    pub message: String,
+    /// Any additional fields attached to the notice.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Notice {
+    /// Creates a notice with just a message.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            extra: Map::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn room_view_reads_nested_and_flat_rooms() {
+        let nested = serde_json::json!({
+            "room": {
+                "id": "r-1",
+                "name": "Town Square",
+                "exits": { "north": "r-2", "east": null }
+            }
+        });
+        let room = RoomView::from_result(nested.as_object().expect("object")).expect("room");
+        assert_eq!(room.id, "r-1");
+        assert_eq!(room.name.as_deref(), Some("Town Square"));
+        assert_eq!(room.exits["north"].as_deref(), Some("r-2"));
+        assert_eq!(room.exits["east"], None);
+
+        let flat = serde_json::json!({
+            "room": "North Gate",
+            "exits": ["south", { "direction": "up", "to": "r-9" }]
+        });
+        let room = RoomView::from_result(flat.as_object().expect("object")).expect("room");
+        assert_eq!(room.id, "North Gate");
+        assert_eq!(room.exits["south"], None);
+        assert_eq!(room.exits["up"].as_deref(), Some("r-9"));
+
+        let item = serde_json::json!({ "name": "Rusty Sword", "damage": 3 });
+        assert_eq!(
+            RoomView::from_result(item.as_object().expect("object")),
+            None
+        );
+    }
+
+    #[test]
+    fn response_distinguishes_success_and_error() {
+        let ok: Response = serde_json::from_value(serde_json::json!({
+            "id": "msg-0001",
+            "room": "Town Square",
+        }))
+        .expect("success parses");
+        assert!(matches!(&ok.body, ResponseBody::Success(body) if body["room"] == "Town Square"));
+
+        let err: Response = serde_json::from_value(serde_json::json!({
+            "id": "msg-0002",
+            "error": { "code": "not_found", "message": "No goblin here." },
+        }))
+        .expect("error parses");
+        let ResponseBody::Error { error } = err.body else {
+            panic!("expected error body");
+        };
+        assert_eq!(error.code, ErrorCode::NotFound);
+        assert_eq!(error.message, "No goblin here.");
+    }
+
+    #[test]
+    fn unknown_error_code_is_preserved() {
+        let error: ServerError = serde_json::from_value(serde_json::json!({
+            "code": "guild_full",
+            "message": "The guild is full.",
+        }))
+        .expect("error parses");
+        assert_eq!(error.code, ErrorCode::Other("guild_full".to_owned()));
+    }
+
+    #[test]
+    fn connect_result_reads_new_account_token() {
+        let response: Response = serde_json::from_value(serde_json::json!({
+            "id": "msg-0001",
+            "new_account": true,
+            "token": "tok-123",
+            "room": "Town Square",
+        }))
+        .expect("response parses");
+        let connect: ConnectResult = response.body.parse().expect("connect result");
+        assert!(connect.new_account);
+        assert_eq!(connect.token.as_deref(), Some("tok-123"));
+    }
+
+    #[test]
+    fn known_event_round_trips_with_extra_fields() {
+        let raw = serde_json::json!({
+            "type": "event",
+            "data": { "event": "player_entered", "player": "Brom", "room_id": "r-12" },
+        });
+        let event = PushEvent::from_message(raw.clone());
+        assert!(matches!(&event, PushEvent::PlayerEntered(m) if m.player == "Brom"));
+        assert_eq!(event.kind(), "player_entered");
+        assert_eq!(event.to_message(), raw);
+    }
+
+    #[test]
+    fn unknown_event_is_kept_verbatim() {
+        let raw = serde_json::json!({
+            "type": "event",
+            "data": { "event": "crafting_complete", "item": "Iron Sword" },
+        });
+        let event = PushEvent::from_message(raw.clone());
+        assert!(matches!(event, PushEvent::Unknown(_)));
+        assert_eq!(event.kind(), "crafting_complete");
+        assert_eq!(event.to_message(), raw);
+    }
+}
@@ -0,0 +1,340 @@
+//! Session transcripts written to disk.
+//!
+//! With `--record`, every command sent to the game server and every
+//! response and push event received is appended as one JSON line to
+//! `<token_path>/sessions/<username>/<started>.jsonl`:
+//!
+//! ```text
+//! {"ts":"2026-10-16T12:30:05.123Z","dir":"sent","msg":{"id":"msg-0001","action":"look","params":{}}}
+//! {"ts":"2026-10-16T12:30:05.180Z","dir":"received","msg":{"id":"msg-0001","result":{...}}}
+//! ```
+//!
+//! Tokens are redacted. A new file is started on each login and whenever
+//! the current one grows too large, and only the most recent files are
+//! kept per character.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::tokens::validate_username;
+
+/// Size at which a transcript is closed and a new one started.
+const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Number of transcripts kept per character.
+const MAX_FILES: usize = 50;
+
+/// Which way a recorded message travelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// A command sent to the game server.
+    Sent,
+    /// A response or push event from the game server.
+    Received,
+}
+
+/// One line of a transcript.
+#[derive(Debug, Serialize)]
+struct Entry<'a> {
+    ts: String,
+    dir: Direction,
+    msg: &'a Value,
+}
+
+/// The transcript currently being written.
+#[derive(Debug)]
+struct Transcript {
+    dir: PathBuf,
+    file: File,
+    written: u64,
+}
+
+#[derive(Debug)]
+struct RecorderInner {
+    root: PathBuf,
+    max_file_bytes: u64,
+    max_files: usize,
+    current: Option<Transcript>,
+}
+
+/// Appends the traffic of the active character's session to disk.
+///
+/// Nothing is written until a character is selected with
+/// [`SessionRecorder::start`].
+#[derive(Debug, Clone)]
+pub struct SessionRecorder {
+    inner: Arc<Mutex<RecorderInner>>,
+}
+
+impl SessionRecorder {
+    /// Creates a recorder that keeps transcripts under `root`.
+    pub fn new(root: PathBuf) -> Self {
+        Self::with_limits(root, MAX_FILE_BYTES, MAX_FILES)
+    }
+
+    fn with_limits(root: PathBuf, max_file_bytes: u64, max_files: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(RecorderInner {
+                root,
+                max_file_bytes,
+                max_files,
+                current: None,
+            })),
+        }
+    }
+
+    /// Starts a new transcript for `username`.
+    ///
+    /// Usernames that could escape the transcript directory are not
+    /// recorded.
+    pub fn start(&self, username: &str) {
+        let mut inner = self.lock();
+        if let Err(err) = validate_username(username) {
+            tracing::warn!(error = %err, "recorder.start.refused");
+            inner.current = None;
+            return;
+        }
+        let dir = inner.root.join(username);
+        inner.current = inner.open(dir);
+    }
+
+    /// Closes the current transcript, e.g. on disconnect.
+    pub fn stop(&self) {
+        self.lock().current = None;
+    }
+
+    /// Appends a message to the current transcript, if any.
+    pub fn record(&self, dir: Direction, message: &Value) {
+        let mut inner = self.lock();
+        let rotate = match &inner.current {
+            Some(transcript) => transcript.written >= inner.max_file_bytes,
+            None => return,
+        };
+        if rotate {
+            let dir = inner.current.take().map(|transcript| transcript.dir);
+            inner.current = dir.and_then(|dir| inner.open(dir));
+        }
+        let Some(transcript) = &mut inner.current else {
+            return;
+        };
+
+        let mut msg = message.clone();
+        redact(&mut msg);
+        let entry = Entry {
+            ts: format_timestamp(SystemTime::now(), ':'),
+            dir,
+            msg: &msg,
+        };
+        let mut line = match serde_json::to_vec(&entry) {
+            Ok(line) => line,
+            Err(err) => {
+                tracing::warn!(error = %err, "recorder.encode.failed");
+                return;
+            }
+        };
+        line.push(b'\n');
+        if let Err(err) = transcript.file.write_all(&line) {
+            tracing::warn!(error = %err, "recorder.write.failed");
+            inner.current = None;
+            return;
+        }
+        transcript.written += line.len() as u64;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RecorderInner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl RecorderInner {
+    /// Opens a fresh transcript in `dir`, pruning the oldest ones.
+    fn open(&self, dir: PathBuf) -> Option<Transcript> {
+        if let Err(err) = std::fs::create_dir_all(&dir) {
+            tracing::warn!(error = %err, path = %dir.display(), "recorder.dir.create.failed");
+            return None;
+        }
+        let path = dir.join(format!(
+            "{}.jsonl",
+            format_timestamp(SystemTime::now(), '-')
+        ));
+        let file = match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => file,
+            Err(err) => {
+                tracing::warn!(error = %err, path = %path.display(), "recorder.file.open.failed");
+                return None;
+            }
+        };
+        tracing::info!(path = %path.display(), "recorder.file.opened");
+        self.prune(&dir);
+        Some(Transcript {
+            dir,
+            file,
+            written: 0,
+        })
+    }
+
+    /// Deletes all but the newest `max_files` transcripts in `dir`.
+    fn prune(&self, dir: &std::path::Path) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut files: Vec<_> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+            .collect();
+        // Names start with the UTC start time, so they sort chronologically.
+        files.sort();
+        let excess = files.len().saturating_sub(self.max_files);
+        for path in &files[..excess] {
+            if let Err(err) = std::fs::remove_file(path) {
+                tracing::warn!(error = %err, path = %path.display(), "recorder.file.remove.failed");
+            }
+        }
+    }
+}
+
+/// Replaces every `token` value with a placeholder.
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                if key == "token" && !item.is_null() {
+                    *item = Value::String("[redacted]".to_owned());
+                } else {
+                    redact(item);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Formats `time` as an RFC 3339 UTC timestamp with milliseconds,
+/// using `time_sep` between hours, minutes and seconds.
+pub fn format_timestamp(time: SystemTime, time_sep: char) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Days since the epoch to a civil date (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}{time_sep}{:02}{time_sep}{:02}.{:03}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis(),
+    )
+}
+
+/// Parses a transcript timestamp as written by [`SessionRecorder::record`].
+pub fn parse_timestamp(text: &str) -> Option<SystemTime> {
+    let (date, time) = text.strip_suffix('Z')?.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<u64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let (hms, millis) = time.split_once('.').unwrap_or((time, "0"));
+    let mut hms = hms.splitn(3, ':').map(str::parse::<u64>);
+    let (hours, minutes, secs) = (hms.next()?.ok()?, hms.next()?.ok()?, hms.next()?.ok()?);
+    let millis: u64 = millis.parse().ok()?;
+    if !(1..=12).contains(&month) || year < 1970 {
+        return None;
+    }
+
+    // Civil date to days since the epoch (inverse of `format_timestamp`).
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = (era * 146_097 + doe).checked_sub(719_468)?;
+
+    let secs = days * 86_400 + hours * 3600 + minutes * 60 + secs;
+    Some(UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_millis(millis))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn transcripts(dir: &std::path::Path) -> Vec<PathBuf> {
+        let mut files: Vec<_> = std::fs::read_dir(dir)
+            .expect("transcript dir")
+            .map(|entry| entry.expect("entry").path())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn timestamps_are_rfc3339_utc() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_251_200_042);
+        assert_eq!(format_timestamp(time, ':'), "2024-03-01T00:00:00.042Z");
+        assert_eq!(parse_timestamp("2024-03-01T00:00:00.042Z"), Some(time));
+        assert_eq!(parse_timestamp("2024-13-01T00:00:00.042Z"), None);
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH, '-'),
+            "1970-01-01T00-00-00.000Z"
+        );
+    }
+
+    #[test]
+    fn traffic_is_recorded_redacted_and_rotated() {
+        let root = std::env::temp_dir().join(format!("wyvern-sessions-{}", std::process::id()));
+        let recorder = SessionRecorder::with_limits(root.clone(), 1, 2);
+
+        // Nothing is recorded before a character is selected.
+        recorder.record(Direction::Sent, &json!({ "action": "look" }));
+        assert!(!root.exists());
+
+        recorder.start("aria");
+        let connect =
+            json!({ "action": "connect", "params": { "username": "aria", "token": "s3cret" } });
+        recorder.record(Direction::Sent, &connect);
+        let files = transcripts(&root.join("aria"));
+        assert_eq!(files.len(), 1);
+        let line: Value = serde_json::from_str(
+            std::fs::read_to_string(&files[0])
+                .expect("transcript")
+                .trim_end(),
+        )
+        .expect("json line");
+        assert_eq!(line["dir"], "sent");
+        assert_eq!(line["msg"]["params"]["username"], "aria");
+        assert_eq!(line["msg"]["params"]["token"], "[redacted]");
+
+        // Every later line overflows the one-byte limit; only two files are kept.
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(2));
+            recorder.record(Direction::Received, &json!({ "type": "event" }));
+        }
+        assert_eq!(transcripts(&root.join("aria")).len(), 2);
+
+        // Names that would leave the transcript directory stop recording.
+        recorder.start("../escaped");
+        recorder.record(Direction::Sent, &connect);
+        assert!(!root.join("../escaped").exists());
+
+        recorder.stop();
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,400 @@
+//! Replays a recorded session as a fake game server.
+//!
+//! `wyvern replay <file>` loads a transcript written by `--record` and
+//! serves it over a local WebSocket. Each request is answered with the
+//! recorded response to the same action and parameters, and recorded
+//! push events are re-sent with their original delay after the command
+//! that preceded them. The MCP side runs unchanged against this server,
+//! so a session can be replayed offline as often as needed.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, watch};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::recorder::{self, Direction};
+
+/// Errors from loading a transcript.
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    /// The transcript could not be read.
+    #[error("failed to read transcript: {0}")]
+    Io(#[from] std::io::Error),
+    /// A line is not a transcript entry.
+    #[error("line {line} is not a transcript entry: {source}")]
+    InvalidLine {
+        /// One-based line number.
+        line: usize,
+        /// Why it failed to parse.
+        source: serde_json::Error,
+    },
+    /// A line's timestamp could not be parsed.
+    #[error("line {line} has an invalid timestamp {ts:?}")]
+    InvalidTimestamp {
+        /// One-based line number.
+        line: usize,
+        /// The offending timestamp.
+        ts: String,
+    },
+}
+
+/// One line of a transcript.
+#[derive(Debug, Deserialize)]
+struct RecordedLine {
+    ts: String,
+    dir: Direction,
+    msg: Value,
+}
+
+/// A recorded command and the response it got.
+#[derive(Debug)]
+struct Exchange {
+    action: String,
+    params: Value,
+    /// The response without its `id`.
+    response: Option<Map<String, Value>>,
+    used: bool,
+}
+
+/// A recorded push event and when to re-send it.
+#[derive(Debug, Clone)]
+struct ScheduledEvent {
+    /// Number of commands sent before the event arrived.
+    after: usize,
+    /// Delay after that command, or after connecting if there was none.
+    delay: Duration,
+    message: Value,
+}
+
+/// A recorded session, ready to be served.
+#[derive(Debug)]
+pub struct Transcript {
+    exchanges: Vec<Exchange>,
+    events: Vec<ScheduledEvent>,
+    /// Number of events already re-sent.
+    played: usize,
+}
+
+impl Transcript {
+    /// Loads a JSONL transcript from `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ReplayError`] if the file cannot be read or a line is
+    /// not a transcript entry.
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    fn parse(text: &str) -> Result<Self, ReplayError> {
+        let mut exchanges = Vec::new();
+        let mut events = Vec::new();
+        let mut pending: HashMap<String, usize> = HashMap::new();
+        let mut start = None;
+        let mut last_sent = None;
+
+        for (index, raw) in text.lines().enumerate() {
+            if raw.trim().is_empty() {
+                continue;
+            }
+            let line = index + 1;
+            let entry: RecordedLine = serde_json::from_str(raw)
+                .map_err(|source| ReplayError::InvalidLine { line, source })?;
+            let ts = recorder::parse_timestamp(&entry.ts)
+                .ok_or(ReplayError::InvalidTimestamp { line, ts: entry.ts })?;
+            let start: SystemTime = *start.get_or_insert(ts);
+            let Value::Object(mut msg) = entry.msg else {
+                continue;
+            };
+
+            let id = msg.get("id").and_then(Value::as_str).map(str::to_owned);
+            match entry.dir {
+                Direction::Sent => {
+                    if let Some(id) = id {
+                        pending.insert(id, exchanges.len());
+                    }
+                    exchanges.push(Exchange {
+                        action: msg
+                            .get("action")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_owned(),
+                        params: msg
+                            .remove("params")
+                            .unwrap_or_else(|| Value::Object(Map::new())),
+                        response: None,
+                        used: false,
+                    });
+                    last_sent = Some(ts);
+                }
+                Direction::Received => {
+                    if let Some(exchange) = id.and_then(|id| pending.remove(&id)) {
+                        msg.remove("id");
+                        exchanges[exchange].response = Some(msg);
+                    } else {
+                        let anchor = last_sent.unwrap_or(start);
+                        events.push(ScheduledEvent {
+                            after: exchanges.len(),
+                            delay: ts.duration_since(anchor).unwrap_or_default(),
+                            message: Value::Object(msg),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            exchanges,
+            events,
+            played: 0,
+        })
+    }
+
+    /// Returns the recorded response to `action` with `params`.
+    ///
+    /// Prefers an unused exchange with identical parameters, then any
+    /// unused exchange for the same action, then an identical exchange
+    /// that was already replayed.
+    fn answer(&mut self, action: &str, params: &Value) -> Option<Map<String, Value>> {
+        // Recorded tokens are redacted, so compare redacted parameters.
+        let mut params = params.clone();
+        recorder::redact(&mut params);
+
+        let candidates = || {
+            self.exchanges
+                .iter()
+                .enumerate()
+                .filter(|(_, exchange)| exchange.action == action && exchange.response.is_some())
+        };
+        let index = candidates()
+            .find(|(_, exchange)| !exchange.used && exchange.params == params)
+            .or_else(|| candidates().find(|(_, exchange)| !exchange.used))
+            .or_else(|| candidates().find(|(_, exchange)| exchange.params == params))
+            .map(|(index, _)| index)?;
+
+        let exchange = &mut self.exchanges[index];
+        exchange.used = true;
+        exchange.response.clone()
+    }
+}
+
+/// Shared state of a running replay.
+#[derive(Debug)]
+struct Replay {
+    transcript: Mutex<Transcript>,
+    /// When each command was received, in order.
+    requests: watch::Sender<Vec<Instant>>,
+}
+
+impl Replay {
+    fn transcript(&self) -> MutexGuard<'_, Transcript> {
+        self.transcript
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Serves `transcript` on a local port and returns its address.
+///
+/// The server runs until the process exits.
+///
+/// # Errors
+///
+/// Returns an error if no local port can be bound.
+pub async fn serve(transcript: Transcript) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tracing::info!(
+        replay.exchanges = transcript.exchanges.len(),
+        replay.events = transcript.events.len(),
+        replay.addr = %addr,
+        "replay.listening"
+    );
+
+    let replay = Arc::new(Replay {
+        transcript: Mutex::new(transcript),
+        requests: watch::Sender::new(Vec::new()),
+    });
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(play(stream, Arc::clone(&replay)));
+        }
+    });
+    Ok(addr)
+}
+
+/// Answers one client connection from the transcript.
+async fn play(stream: TcpStream, replay: Arc<Replay>) {
+    let ws = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(err) => {
+            tracing::warn!(error = %err, "replay.handshake.failed");
+            return;
+        }
+    };
+    let (mut ws_write, mut ws_read) = ws.split();
+
+    let (write_tx, mut write_rx) = mpsc::channel::<Message>(64);
+    tokio::spawn(async move {
+        while let Some(msg) = write_rx.recv().await {
+            if ws_write.send(msg).await.is_err() {
+                break;
+            }
+        }
+    });
+    let events = tokio::spawn(play_events(
+        Arc::clone(&replay),
+        write_tx.clone(),
+        Instant::now(),
+    ));
+
+    while let Some(Ok(msg)) = ws_read.next().await {
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let received = Instant::now();
+        let Ok(request) = serde_json::from_str::<Value>(&text) else {
+            tracing::warn!("replay.request.invalid_json");
+            continue;
+        };
+        let action = request["action"].as_str().unwrap_or_default();
+        let params = request
+            .get("params")
+            .cloned()
+            .unwrap_or_else(|| Value::Object(Map::new()));
+
+        let answer = replay.transcript().answer(action, &params);
+        let mut response = answer.unwrap_or_else(|| {
+            tracing::warn!(msg.action = action, "replay.request.unmatched");
+            let error = serde_json::json!({
+                "code": "replay_miss",
+                "message": format!("the transcript has no response to `{action}`"),
+            });
+            Map::from_iter([("error".to_owned(), error)])
+        });
+        response.insert("id".to_owned(), request["id"].clone());
+        if write_tx
+            .send(Message::Text(Value::Object(response).to_string().into()))
+            .await
+            .is_err()
+        {
+            break;
+        }
+        // Only now release events recorded after this command.
+        replay.requests.send_modify(|sent| sent.push(received));
+    }
+    events.abort();
+}
+
+/// Re-sends recorded push events on their original timing.
+async fn play_events(replay: Arc<Replay>, write_tx: mpsc::Sender<Message>, connected: Instant) {
+    let mut requests = replay.requests.subscribe();
+    loop {
+        let next = {
+            let transcript = replay.transcript();
+            transcript.events.get(transcript.played).cloned()
+        };
+        let Some(event) = next else {
+            break;
+        };
+
+        let anchor = match event.after {
+            0 => connected,
+            after => match requests.wait_for(|sent| sent.len() >= after).await {
+                Ok(sent) => sent[after - 1],
+                Err(_) => break,
+            },
+        };
+        tokio::time::sleep_until(anchor + event.delay).await;
+        let msg = Message::Text(event.message.to_string().into());
+        if write_tx.send(msg).await.is_err() {
+            break;
+        }
+        replay.transcript().played += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::connection::{ConnectionOptions, GameConnection};
+    use crate::protocol::{ErrorCode, PushEvent, ResponseBody};
+
+    const TRANSCRIPT: &str = r#"
+{"ts":"2026-10-16T12:00:00.000Z","dir":"sent","msg":{"id":"msg-0001","action":"connect","params":{"username":"aria","token":"[redacted]"}}}
+{"ts":"2026-10-16T12:00:00.010Z","dir":"received","msg":{"id":"msg-0001","room":"Town Square"}}
+{"ts":"2026-10-16T12:00:00.050Z","dir":"received","msg":{"type":"event","data":{"event":"tell","from":"bob","message":"hi"}}}
+{"ts":"2026-10-16T12:00:01.000Z","dir":"sent","msg":{"id":"msg-0002","action":"look","params":{"target":"bob"}}}
+{"ts":"2026-10-16T12:00:01.010Z","dir":"received","msg":{"id":"msg-0002","name":"Bob"}}
+{"ts":"2026-10-16T12:00:02.000Z","dir":"sent","msg":{"id":"msg-0003","action":"look","params":{}}}
+{"ts":"2026-10-16T12:00:02.010Z","dir":"received","msg":{"id":"msg-0003","room":"Town Square"}}
+"#;
+
+    #[test]
+    fn requests_match_by_action_then_params() {
+        let mut transcript = Transcript::parse(TRANSCRIPT).expect("transcript");
+        assert_eq!(transcript.events.len(), 1);
+        assert_eq!(transcript.events[0].after, 1);
+        assert_eq!(transcript.events[0].delay, Duration::from_millis(50));
+
+        // Exact match first, even out of order.
+        let room = transcript.answer("look", &json!({})).expect("answer");
+        assert_eq!(room["room"], "Town Square");
+        // Then any unused response to the same action.
+        let bob = transcript
+            .answer("look", &json!({ "target": "carol" }))
+            .expect("answer");
+        assert_eq!(bob["name"], "Bob");
+        // Then an identical response again.
+        assert!(transcript.answer("look", &json!({})).is_some());
+        assert!(transcript.answer("map", &json!({})).is_none());
+
+        assert!(matches!(
+            Transcript::parse("{\"ts\":\"yesterday\",\"dir\":\"sent\",\"msg\":{}}"),
+            Err(ReplayError::InvalidTimestamp { line: 1, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn replays_responses_and_timed_events() {
+        let transcript = Transcript::parse(TRANSCRIPT).expect("transcript");
+        let addr = serve(transcript).await.expect("serve");
+
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut conn = GameConnection::new(event_tx, ConnectionOptions::default());
+        conn.connect(format!("ws://{addr}/ws"))
+            .await
+            .expect("connect");
+
+        let connect = json!({ "username": "aria", "token": "any" });
+        let response = conn.send_command("connect", connect).await.expect("reply");
+        assert!(
+            matches!(response.body, ResponseBody::Success(ref room) if room["room"] == "Town Square")
+        );
+
+        let event = tokio::time::timeout(Duration::from_secs(5), event_rx.recv())
+            .await
+            .expect("event in time");
+        assert!(matches!(event, Some(PushEvent::Tell(_))));
+
+        let response = conn.send_command("map", json!({})).await.expect("reply");
+        assert!(matches!(
+            response.body,
+            ResponseBody::Error { error } if error.code == ErrorCode::Other("replay_miss".to_owned())
+        ));
+        conn.disconnect().await;
+    }
+}
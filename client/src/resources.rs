@@ -0,0 +1,372 @@
+//! Game state exposed as MCP resources.
+//!
+//! Claude can read `wyvern://status`, `wyvern://room` and friends
+//! without spending a tool call. Each resource is served from a
+//! client-side cache that is refreshed from the responses to ordinary
+//! tool calls and invalidated by push events and state-changing
+//! actions. A resource missing from the cache is fetched from the
+//! server on read.
+//!
+//! Each client that subscribes to a resource receives
+//! `notifications/resources/updated` when a value it may have read
+//! changes or goes stale; clients that did not subscribe hear nothing.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use rmcp::model::{AnnotateAble, RawResource, Resource, ResourceUpdatedNotificationParam};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use crate::notify::Peers;
+use crate::protocol::{PushEvent, ResponseBody};
+
+/// A piece of game state published as an MCP resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameResource {
+    /// HP, mana, level, XP and active effects.
+    Status,
+    /// Carried and equipped items.
+    Inventory,
+    /// The current room: description, exits, occupants and items.
+    Room,
+    /// ASCII map of nearby explored rooms.
+    Map,
+    /// Active and completed quests.
+    Quests,
+}
+
+impl GameResource {
+    /// Every published resource, in listing order.
+    pub const ALL: [Self; 5] = [
+        Self::Status,
+        Self::Inventory,
+        Self::Room,
+        Self::Map,
+        Self::Quests,
+    ];
+
+    /// Returns the resource's URI, e.g. `wyvern://status`.
+    pub fn uri(self) -> &'static str {
+        match self {
+            Self::Status => "wyvern://status",
+            Self::Inventory => "wyvern://inventory",
+            Self::Room => "wyvern://room",
+            Self::Map => "wyvern://map",
+            Self::Quests => "wyvern://quests",
+        }
+    }
+
+    /// Looks up a resource by URI.
+    pub fn from_uri(uri: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|resource| resource.uri() == uri)
+    }
+
+    /// Returns the server action that fetches this resource.
+    pub fn action(self) -> &'static str {
+        match self {
+            Self::Status => "status",
+            Self::Inventory => "inventory",
+            Self::Room => "look",
+            Self::Map => "map",
+            Self::Quests => "quests",
+        }
+    }
+
+    /// Describes the resource for `resources/list`.
+    pub fn to_resource(self) -> Resource {
+        let (name, description) = match self {
+            Self::Status => (
+                "status",
+                "Your character's HP, mana, level, XP, eq/balance and active effects.",
+            ),
+            Self::Inventory => ("inventory", "Items you carry and have equipped."),
+            Self::Room => (
+                "room",
+                "The room you are in: description, exits, players, NPCs and items.",
+            ),
+            Self::Map => ("map", "ASCII map of nearby explored rooms."),
+            Self::Quests => ("quests", "Your active and completed quests."),
+        };
+        let mut resource = RawResource::new(self.uri(), name);
+        resource.description = Some(description.to_owned());
+        resource.mime_type = Some("application/json".to_owned());
+        resource.no_annotation()
+    }
+}
+
+/// Resources refreshed and invalidated by a successful action.
+///
+/// The first element is the resource whose full state the response
+/// carries; the second lists resources the action may have changed.
+fn action_effects(action: &str, params: &Value) -> (Option<GameResource>, &'static [GameResource]) {
+    use GameResource::{Inventory, Map, Quests, Room, Status};
+
+    match action {
+        "status" => (Some(Status), &[]),
+        "inventory" => (Some(Inventory), &[]),
+        // `look <target>` examines something instead of describing the room.
+        "look" if params.get("target").is_none() => (Some(Room), &[]),
+        "move" => (Some(Room), &[Map, Status]),
+        "map" => (Some(Map), &[]),
+        "quests" => (Some(Quests), &[]),
+        "attack" | "use_ability" => (None, &[Status, Room]),
+        "flee" => (None, &[Status, Room, Map]),
+        "get" | "drop" => (None, &[Inventory, Room]),
+        "equip" | "use_item" | "buy" | "sell" => (None, &[Inventory, Status]),
+        "accept_quest" => (None, &[Quests]),
+        "complete_quest" => (None, &[Quests, Inventory, Status]),
+        _ => (None, &[]),
+    }
+}
+
+/// Resources a push event may have changed.
+fn event_effects(event: &PushEvent) -> &'static [GameResource] {
+    use GameResource::{Map, Room, Status};
+
+    match event {
+        PushEvent::PlayerEntered(_) | PushEvent::PlayerLeft(_) => &[Room],
+        PushEvent::CombatUpdate(_) | PushEvent::LevelUp(_) => &[Status],
+        PushEvent::Death(_) => &[Status, Room, Map],
+        _ => &[],
+    }
+}
+
+#[derive(Debug, Default)]
+struct StateInner {
+    cached: HashMap<GameResource, Value>,
+}
+
+impl StateInner {
+    /// Stores a fresh value, returning true if it replaced a different one.
+    ///
+    /// Filling an empty slot is not a change: either nobody has read the
+    /// resource yet, or its invalidation was already announced.
+    fn store(&mut self, resource: GameResource, value: Value) -> bool {
+        let changed = self
+            .cached
+            .get(&resource)
+            .is_some_and(|previous| *previous != value);
+        self.cached.insert(resource, value);
+        changed
+    }
+
+    /// Drops a cached value, returning true if there was one.
+    fn invalidate(&mut self, resource: GameResource) -> bool {
+        self.cached.remove(&resource).is_some()
+    }
+}
+
+/// Client-side cache of the game state behind the published resources.
+#[derive(Debug, Clone, Default)]
+pub struct GameState {
+    inner: Arc<Mutex<StateInner>>,
+}
+
+impl GameState {
+    /// Returns the cached value of `resource`, if any.
+    pub fn get(&self, resource: GameResource) -> Option<Value> {
+        self.lock().cached.get(&resource).cloned()
+    }
+
+    /// Updates the cache from the response to `action`.
+    ///
+    /// Returns the resources that changed or went stale.
+    pub fn record_response(
+        &self,
+        action: &str,
+        params: &Value,
+        body: &ResponseBody,
+    ) -> Vec<GameResource> {
+        // A refused action changed nothing.
+        let ResponseBody::Success(result) = body else {
+            return Vec::new();
+        };
+
+        let (snapshot, stale) = action_effects(action, params);
+        let mut inner = self.lock();
+        let mut changed = Vec::new();
+        if let Some(resource) = snapshot {
+            if inner.store(resource, Value::Object(result.clone())) {
+                changed.push(resource);
+            }
+        }
+        for &resource in stale {
+            if inner.invalidate(resource) {
+                changed.push(resource);
+            }
+        }
+        changed
+    }
+
+    /// Invalidates the resources affected by a push event.
+    ///
+    /// Returns the resources that went stale.
+    pub fn record_event(&self, event: &PushEvent) -> Vec<GameResource> {
+        let mut inner = self.lock();
+        event_effects(event)
+            .iter()
+            .copied()
+            .filter(|&resource| inner.invalidate(resource))
+            .collect()
+    }
+
+    /// Forgets all cached state, e.g. when the session ends.
+    ///
+    /// Returns the resources that went stale.
+    pub fn clear(&self) -> Vec<GameResource> {
+        let mut inner = self.lock();
+        inner.cached.drain().map(|(resource, _)| resource).collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, StateInner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The resources one MCP client subscribed to.
+#[derive(Debug, Clone, Default)]
+pub struct Subscriptions {
+    inner: Arc<Mutex<HashSet<GameResource>>>,
+}
+
+impl Subscriptions {
+    /// Starts sending update notifications for `resource`.
+    pub fn subscribe(&self, resource: GameResource) {
+        self.lock().insert(resource);
+    }
+
+    /// Stops sending update notifications for `resource`.
+    pub fn unsubscribe(&self, resource: GameResource) {
+        self.lock().remove(&resource);
+    }
+
+    /// Returns true if update notifications for `resource` are wanted.
+    pub fn contains(&self, resource: GameResource) -> bool {
+        self.lock().contains(&resource)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashSet<GameResource>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Tells each client in `peers` which of the given resources it
+/// subscribed to changed.
+pub async fn notify_updated(peers: &Peers, resources: Vec<GameResource>) {
+    if resources.is_empty() {
+        return;
+    }
+    for (peer, subscriptions) in peers.live_with_subscriptions() {
+        for resource in &resources {
+            if !subscriptions.contains(*resource) {
+                continue;
+            }
+            let param = ResourceUpdatedNotificationParam {
+                uri: resource.uri().to_owned(),
+            };
+            if let Err(err) = peer.notify_resource_updated(param).await {
+                tracing::warn!(error = %err, resource.uri = resource.uri(), "mcp.notify.resource_updated.failed");
+            }
+        }
+    }
+}
+
+/// Invalidates cached state as push events arrive, until the feed closes.
+///
+/// The state describes one character, so events are only tracked while
+/// `is_active` says the feed is that character's.
+pub async fn track_events(
+    mut feed: broadcast::Receiver<PushEvent>,
+    state: GameState,
+    peers: Peers,
+    is_active: impl Fn() -> bool,
+) {
+    loop {
+        let changed = match feed.recv().await {
+            Err(broadcast::error::RecvError::Closed) => break,
+            _ if !is_active() => continue,
+            Ok(event) => state.record_event(&event),
+            // Missed events may have changed anything.
+            Err(broadcast::error::RecvError::Lagged(_)) => state.clear(),
+        };
+        notify_updated(&peers, changed).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn body(value: Value) -> ResponseBody {
+        serde_json::from_value(value).expect("valid response body")
+    }
+
+    #[test]
+    fn uris_round_trip() {
+        for resource in GameResource::ALL {
+            assert_eq!(GameResource::from_uri(resource.uri()), Some(resource));
+        }
+        assert_eq!(GameResource::from_uri("wyvern://nope"), None);
+    }
+
+    #[test]
+    fn responses_refresh_and_invalidate_the_cache() {
+        let state = GameState::default();
+
+        let room = body(json!({ "room": "Town Square" }));
+        // First fill is not announced as a change.
+        assert!(state.record_response("look", &json!({}), &room).is_empty());
+        assert_eq!(
+            state.get(GameResource::Room),
+            Some(json!({ "room": "Town Square" }))
+        );
+
+        // Examining a target says nothing about the room.
+        let sword = body(json!({ "item": "sword" }));
+        state.record_response("look", &json!({ "target": "sword" }), &sword);
+        assert_eq!(
+            state.get(GameResource::Room),
+            Some(json!({ "room": "Town Square" }))
+        );
+
+        let moved = body(json!({ "room": "North Gate" }));
+        let changed = state.record_response("move", &json!({ "direction": "north" }), &moved);
+        assert_eq!(changed, vec![GameResource::Room]);
+
+        let refused = body(json!({ "error": { "code": "not_found", "message": "no" } }));
+        assert!(state
+            .record_response("drop", &json!({}), &refused)
+            .is_empty());
+        assert!(state.get(GameResource::Room).is_some());
+
+        state.record_response("get", &json!({ "item": "sword" }), &sword);
+        assert_eq!(state.get(GameResource::Room), None);
+    }
+
+    #[test]
+    fn events_invalidate_the_affected_resources() {
+        let state = GameState::default();
+        let status = body(json!({ "hp": 10 }));
+        state.record_response("status", &json!({}), &status);
+
+        let hit = PushEvent::from_message(json!({
+            "type": "event",
+            "data": {
+                "event": "combat_update",
+                "attacker": "Goblin",
+                "target": "aria",
+                "damage": 3,
+                "hp": 7,
+                "max_hp": 10
+            }
+        }));
+        assert_eq!(state.record_event(&hit), vec![GameResource::Status]);
+        assert_eq!(state.get(GameResource::Status), None);
+
+        // Nothing cached, so nothing went stale.
+        assert!(state.record_event(&hit).is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+//! Several characters logged in at once from one MCP server.
+//!
+//! Each `connect` opens a session for that character (or reopens its
+//! existing one) with its own game connection, event buffer, map,
+//! triggers, aliases, event filter and transcript, and makes it the
+//! active session.
+//! Tools act on the active session unless a call names another in its
+//! `session` argument; [`with_selected`] scopes that choice to the call.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex as StdMutex, MutexGuard, PoisonError};
+
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::aliases::Aliases;
+use crate::connection::{ConnectionOptions, GameConnection, LinkMonitor};
+use crate::events::EventBuffer;
+use crate::filter::EventFilter;
+use crate::recorder::SessionRecorder;
+use crate::triggers::Triggers;
+use crate::world::WorldTracker;
+
+tokio::task_local! {
+    /// Session named by the `session` argument of the tool call in progress.
+    static SELECTED: String;
+}
+
+/// Runs `call` with tools acting on the session `name`.
+pub async fn with_selected<F: Future>(name: String, call: F) -> F::Output {
+    SELECTED.scope(name, call).await
+}
+
+/// Returns the session selected for the tool call in progress, if any.
+pub fn selected() -> Option<String> {
+    SELECTED.try_with(Clone::clone).ok()
+}
+
+/// One character's link to the game and the state kept for it.
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// Character name, which also names the session.
+    pub name: String,
+    /// WebSocket link to the game server.
+    pub connection: Arc<Mutex<GameConnection>>,
+    /// Link health, readable without locking the connection.
+    pub link: LinkMonitor,
+    /// Push events not yet reported with a tool response.
+    pub events: Arc<Mutex<EventBuffer>>,
+    /// The character's explored map.
+    pub world: WorldTracker,
+    /// The character's triggers.
+    pub triggers: Triggers,
+    /// The character's aliases.
+    pub aliases: Aliases,
+    /// Transcript writer, with `--record`.
+    pub recorder: Option<SessionRecorder>,
+    /// Which events tool responses report in full.
+    pub filter: Arc<StdMutex<EventFilter>>,
+    /// Background tasks watching the session's events.
+    tasks: Arc<StdMutex<Vec<JoinHandle<()>>>>,
+}
+
+impl Session {
+    /// Creates an unconnected session for `name`.
+    pub fn new(name: String, options: ConnectionOptions, filter: EventFilter) -> Self {
+        let (event_tx, event_rx) = tokio::sync::mpsc::unbounded_channel();
+        let recorder = options.recorder.clone();
+        let connection = GameConnection::new(event_tx, options);
+        Self {
+            name,
+            link: connection.monitor(),
+            connection: Arc::new(Mutex::new(connection)),
+            events: Arc::new(Mutex::new(EventBuffer::new(event_rx))),
+            world: WorldTracker::default(),
+            triggers: Triggers::default(),
+            aliases: Aliases::default(),
+            recorder,
+            filter: Arc::new(StdMutex::new(filter)),
+            tasks: Arc::default(),
+        }
+    }
+
+    /// Runs `task` in the background until the session is closed.
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        let handle = tokio::spawn(task);
+        self.tasks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(handle);
+    }
+
+    /// Stops every task started with [`Session::spawn`].
+    pub fn abort_tasks(&self) {
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap_or_else(PoisonError::into_inner));
+        for task in tasks {
+            task.abort();
+        }
+    }
+
+    /// Returns a copy of the session's event filter.
+    pub fn filter(&self) -> EventFilter {
+        self.filter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+#[derive(Debug, Default)]
+struct SessionsInner {
+    open: BTreeMap<String, Session>,
+    active: Option<String>,
+}
+
+/// Open sessions by character name, and which one is active.
+#[derive(Debug, Clone, Default)]
+pub struct Sessions {
+    inner: Arc<StdMutex<SessionsInner>>,
+}
+
+impl Sessions {
+    /// Returns the session for `name`, if open.
+    pub fn get(&self, name: &str) -> Option<Session> {
+        self.lock().open.get(name).cloned()
+    }
+
+    /// Returns the name of the active session.
+    pub fn active_name(&self) -> Option<String> {
+        self.lock().active.clone()
+    }
+
+    /// Returns every open session, ordered by name.
+    pub fn all(&self) -> Vec<Session> {
+        self.lock().open.values().cloned().collect()
+    }
+
+    /// Adds a session and makes it the active one.
+    pub fn insert(&self, session: Session) {
+        let mut inner = self.lock();
+        inner.active = Some(session.name.clone());
+        inner.open.insert(session.name.clone(), session);
+    }
+
+    /// Makes `name` the active session; false if it is not open.
+    pub fn activate(&self, name: &str) -> bool {
+        let mut inner = self.lock();
+        if !inner.open.contains_key(name) {
+            return false;
+        }
+        inner.active = Some(name.to_owned());
+        true
+    }
+
+    /// Removes the session for `name`. If it was active, another open
+    /// session (if any) becomes active.
+    pub fn remove(&self, name: &str) -> Option<Session> {
+        let mut inner = self.lock();
+        let session = inner.open.remove(name)?;
+        if inner.active.as_deref() == Some(name) {
+            inner.active = inner.open.keys().next().cloned();
+        }
+        Some(session)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SessionsInner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn removing_the_active_session_activates_another() {
+        let sessions = Sessions::default();
+        let open = |name: &str| {
+            Session::new(
+                name.to_owned(),
+                ConnectionOptions::default(),
+                EventFilter::default(),
+            )
+        };
+        sessions.insert(open("aria"));
+        sessions.insert(open("brom"));
+        assert_eq!(sessions.active_name().as_deref(), Some("brom"));
+
+        assert!(sessions.activate("aria"));
+        assert!(!sessions.activate("cato"));
+        assert!(sessions.remove("brom").is_some());
+        assert_eq!(sessions.active_name().as_deref(), Some("aria"));
+        assert!(sessions.remove("aria").is_some());
+        assert_eq!(sessions.active_name(), None);
+
+        let session = open("cato");
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel::<()>();
+        session.spawn(async move {
+            std::future::pending::<()>().await;
+            drop(done_tx);
+        });
+        session.abort_tasks();
+        assert!(done_rx.await.is_err(), "task dropped by abort");
+
+        assert_eq!(selected(), None);
+        let name = with_selected("brom".to_owned(), async { selected() }).await;
+        assert_eq!(name.as_deref(), Some("brom"));
+    }
+}
@@ -0,0 +1,107 @@
+//! Per-character JSON files on disk.
+//!
+//! Maps, triggers and aliases are each kept in one JSON file per
+//! character under the token path. Files are replaced atomically, so a
+//! crash mid-save leaves the previous contents rather than a truncated
+//! file, and a file that cannot be parsed is set aside as `*.corrupt`
+//! rather than overwritten by the next save.
+
+use std::io::Write;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Reads the value stored at `file`, or the default if it is missing or
+/// cannot be parsed.
+///
+/// A file that cannot be parsed is renamed to `<file>.corrupt`, so it can
+/// still be recovered by hand after the next save.
+pub fn load<T: DeserializeOwned + Default>(file: &Path) -> T {
+    let Ok(contents) = std::fs::read_to_string(file) else {
+        return T::default();
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|err| {
+        let mut corrupt = file.as_os_str().to_owned();
+        corrupt.push(".corrupt");
+        tracing::warn!(
+            error = %err,
+            path = %file.display(),
+            moved_to = %Path::new(&corrupt).display(),
+            "store.file.invalid"
+        );
+        if let Err(err) = std::fs::rename(file, &corrupt) {
+            tracing::warn!(error = %err, path = %file.display(), "store.file.set_aside.failed");
+        }
+        T::default()
+    })
+}
+
+/// Writes `value` to `file`, creating directories as needed.
+///
+/// Failures are logged rather than returned: losing a save is better
+/// than failing the tool call that caused it.
+pub fn save<T: Serialize>(file: &Path, value: &T) {
+    let contents = match serde_json::to_string_pretty(value) {
+        Ok(contents) => contents,
+        Err(err) => {
+            tracing::warn!(error = %err, path = %file.display(), "store.encode.failed");
+            return;
+        }
+    };
+    if let Err(err) = write_atomically(file, contents.as_bytes()) {
+        tracing::warn!(error = %err, path = %file.display(), "store.file.write.failed");
+    }
+}
+
+/// Writes `contents` beside `file` and renames it over the original.
+fn write_atomically(file: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let dir = file.parent().unwrap_or_else(|| Path::new("."));
+    std::fs::create_dir_all(dir)?;
+    let name = file
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let tmp = dir.join(format!(".{name}.tmp"));
+    let mut handle = std::fs::File::create(&tmp)?;
+    handle.write_all(contents)?;
+    handle.sync_all()?;
+    std::fs::rename(&tmp, file)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn saves_replace_the_file_and_bad_files_load_as_default() {
+        let dir = std::env::temp_dir().join(format!("wyvern-store-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let file = dir.join("maps").join("aria.json");
+        assert!(load::<BTreeMap<String, u32>>(&file).is_empty());
+
+        save(&file, &BTreeMap::from([("a".to_owned(), 1)]));
+        save(&file, &BTreeMap::from([("b".to_owned(), 2)]));
+        let loaded: BTreeMap<String, u32> = load(&file);
+        assert_eq!(loaded, BTreeMap::from([("b".to_owned(), 2)]));
+        let names: Vec<_> = std::fs::read_dir(dir.join("maps"))
+            .expect("dir")
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name())
+            .collect();
+        assert_eq!(names, ["aria.json"]);
+
+        // A bad file survives the next save under another name.
+        std::fs::write(&file, "{not json").expect("written");
+        assert!(load::<BTreeMap<String, u32>>(&file).is_empty());
+        save(&file, &BTreeMap::from([("c".to_owned(), 3)]));
+        let corrupt = dir.join("maps").join("aria.json.corrupt");
+        assert_eq!(
+            std::fs::read_to_string(corrupt).expect("set aside"),
+            "{not json"
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
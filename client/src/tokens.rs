@@ -0,0 +1,412 @@
+//! Per-character authentication tokens on disk.
+//!
+//! Each character's token lives in `<token_path>/tokens/<username>`. Files
+//! are replaced atomically and, on Unix, created `0600` inside a `0700`
+//! directory. When `WYVERN_TOKEN_PASSPHRASE` is set, tokens are written
+//! encrypted with ChaCha20-Poly1305 under a key derived from the
+//! passphrase with Argon2id; plain tokens written earlier still load.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+/// Environment variable holding the token passphrase.
+pub const PASSPHRASE_ENV: &str = "WYVERN_TOKEN_PASSPHRASE";
+
+/// Marks an encrypted token file: `<prefix><salt>:<nonce>:<ciphertext>`,
+/// each part hex-encoded.
+const ENCRYPTED_PREFIX: &str = "wyvern-enc-v1:";
+
+const SALT_LEN: usize = 16;
+
+/// Errors from reading or writing tokens.
+#[derive(Debug, thiserror::Error)]
+pub enum TokenError {
+    /// The token file or directory could not be accessed.
+    #[error("token storage failed: {0}")]
+    Io(#[from] std::io::Error),
+    /// The token is encrypted and no passphrase is set.
+    #[error("the token for {username} is encrypted; set {PASSPHRASE_ENV} to use it")]
+    Locked {
+        /// Character the token belongs to.
+        username: String,
+    },
+    /// The username cannot name a token file.
+    #[error("invalid username {0:?}")]
+    InvalidUsername(String),
+    /// The token could not be decrypted with the passphrase.
+    #[error("the token for {username} could not be decrypted; wrong passphrase or corrupt file")]
+    Undecryptable {
+        /// Character the token belongs to.
+        username: String,
+    },
+    /// Deriving the key or encrypting the token failed.
+    #[error("token encryption failed: {0}")]
+    Crypto(String),
+}
+
+/// Passphrase tokens are encrypted with; never printed.
+#[derive(Clone)]
+pub struct Passphrase(String);
+
+impl Passphrase {
+    /// Reads the passphrase from [`PASSPHRASE_ENV`], if set and non-empty.
+    pub fn from_env() -> Option<Self> {
+        std::env::var(PASSPHRASE_ENV)
+            .ok()
+            .filter(|passphrase| !passphrase.is_empty())
+            .map(Self)
+    }
+}
+
+impl std::fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Passphrase(..)")
+    }
+}
+
+/// Token files under one token path.
+#[derive(Debug, Clone)]
+pub struct TokenStore {
+    root: PathBuf,
+    passphrase: Option<Passphrase>,
+}
+
+impl TokenStore {
+    /// Creates a store for tokens under `<root>/tokens`, encrypting new
+    /// tokens with `passphrase` if given.
+    pub fn new(root: PathBuf, passphrase: Option<Passphrase>) -> Self {
+        Self { root, passphrase }
+    }
+
+    fn dir(&self) -> PathBuf {
+        self.root.join("tokens")
+    }
+
+    /// Returns the token file for `username`, refusing names that would
+    /// escape the token directory.
+    fn path(&self, username: &str) -> Result<PathBuf, TokenError> {
+        validate_username(username)?;
+        Ok(self.dir().join(username))
+    }
+
+    /// Returns the usernames with a saved token, sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TokenError`] if the token directory cannot be listed.
+    pub fn list(&self) -> Result<Vec<String>, TokenError> {
+        let entries = match std::fs::read_dir(self.dir()) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut usernames = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            // Skips in-progress writes and anything not named by a username.
+            if let Some(name) = entry
+                .file_name()
+                .to_str()
+                .filter(|name| !name.starts_with('.'))
+            {
+                usernames.push(name.to_owned());
+            }
+        }
+        usernames.sort();
+        Ok(usernames)
+    }
+
+    /// Deletes the token saved for `username`; `false` if there was none.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TokenError`] if the file exists but cannot be removed.
+    pub fn forget(&self, username: &str) -> Result<bool, TokenError> {
+        match std::fs::remove_file(self.path(username)?) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the token saved for `username`, if any.
+    ///
+    /// Falls back to the legacy single-file `<root>/token`, which is moved
+    /// to `username` once it has been saved in its place.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TokenError`] if the file cannot be read or decrypted.
+    pub fn read(&self, username: &str) -> Result<Option<String>, TokenError> {
+        if let Some(token) = self.read_file(&self.path(username)?, username)? {
+            return Ok(Some(token));
+        }
+
+        let legacy = self.root.join("token");
+        let Some(token) = self.read_file(&legacy, username)? else {
+            return Ok(None);
+        };
+        match self.write(username, &token) {
+            Ok(()) => {
+                std::fs::remove_file(&legacy)?;
+                tracing::info!(username, path = %legacy.display(), "token.legacy_file.migrated");
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, path = %legacy.display(), "token.legacy_file.kept");
+            }
+        }
+        Ok(Some(token))
+    }
+
+    /// Saves `token` for `username`, replacing any previous one.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TokenError`] if the token cannot be encrypted or written.
+    pub fn write(&self, username: &str, token: &str) -> Result<(), TokenError> {
+        let contents = match &self.passphrase {
+            Some(passphrase) => seal(passphrase, token)?,
+            None => token.to_owned(),
+        };
+        let path = self.path(username)?;
+        let dir = self.dir();
+        create_private_dir(&dir)?;
+
+        // Write beside the target and rename over it, so a crash never
+        // leaves a truncated token behind.
+        let tmp = dir.join(format!(".{username}.tmp"));
+        let _ = std::fs::remove_file(&tmp);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Reads and decodes one token file; `None` if it is missing or empty.
+    fn read_file(&self, path: &Path, username: &str) -> Result<Option<String>, TokenError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        warn_if_exposed(path);
+        let contents = contents.trim();
+        let token = match contents.strip_prefix(ENCRYPTED_PREFIX) {
+            Some(sealed) => {
+                let Some(passphrase) = &self.passphrase else {
+                    return Err(TokenError::Locked {
+                        username: username.to_owned(),
+                    });
+                };
+                open(passphrase, sealed).ok_or_else(|| TokenError::Undecryptable {
+                    username: username.to_owned(),
+                })?
+            }
+            None => contents.to_owned(),
+        };
+        Ok((!token.is_empty()).then_some(token))
+    }
+}
+
+/// Checks that `username` can name a file in a per-character directory:
+/// not empty, not hidden, and without path separators.
+///
+/// # Errors
+///
+/// Returns [`TokenError::InvalidUsername`] for any other name.
+pub fn validate_username(username: &str) -> Result<(), TokenError> {
+    if username.is_empty() || username.starts_with('.') || username.contains(['/', '\\']) {
+        return Err(TokenError::InvalidUsername(username.to_owned()));
+    }
+    Ok(())
+}
+
+/// Creates `dir` if needed and restricts it to its owner.
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        builder.mode(0o700).create(dir)?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+    }
+    #[cfg(not(unix))]
+    builder.create(dir)
+}
+
+/// Warns if other users can access the token file at `path`.
+fn warn_if_exposed(path: &Path) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            let mode = metadata.permissions().mode() & 0o777;
+            if mode & 0o077 != 0 {
+                tracing::warn!(
+                    path = %path.display(),
+                    mode = format_args!("{mode:o}"),
+                    "token.file.exposed"
+                );
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// Derives the cipher for `salt` from the passphrase.
+fn cipher(passphrase: &Passphrase, salt: &[u8]) -> Result<ChaCha20Poly1305, TokenError> {
+    let mut key = Key::default();
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.0.as_bytes(), salt, &mut key)
+        .map_err(|err| TokenError::Crypto(err.to_string()))?;
+    Ok(ChaCha20Poly1305::new(&key))
+}
+
+/// Encrypts `token` under a fresh salt and nonce.
+fn seal(passphrase: &Passphrase, token: &str) -> Result<String, TokenError> {
+    let mut salt = [0; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(&nonce, token.as_bytes())
+        .map_err(|err| TokenError::Crypto(err.to_string()))?;
+    Ok(format!(
+        "{ENCRYPTED_PREFIX}{}:{}:{}",
+        to_hex(&salt),
+        to_hex(&nonce),
+        to_hex(&ciphertext)
+    ))
+}
+
+/// Decrypts the part of a token file after [`ENCRYPTED_PREFIX`].
+fn open(passphrase: &Passphrase, sealed: &str) -> Option<String> {
+    let mut parts = sealed.splitn(3, ':').map(from_hex);
+    let (salt, nonce, ciphertext) = (parts.next()??, parts.next()??, parts.next()??);
+    if nonce.len() != 12 {
+        return None;
+    }
+    let plaintext = cipher(passphrase, &salt)
+        .ok()?
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .ok()?;
+    String::from_utf8(plaintext).ok()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write as _;
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("wyvern-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        root
+    }
+
+    #[test]
+    fn tokens_are_private_and_legacy_files_migrate() {
+        let root = temp_root("tokens-plain");
+        let store = TokenStore::new(root.clone(), None);
+        assert!(store.read("aria").expect("readable").is_none());
+
+        std::fs::create_dir_all(&root).expect("root");
+        std::fs::write(root.join("token"), "legacy\n").expect("legacy token");
+        assert_eq!(
+            store.read("aria").expect("readable").as_deref(),
+            Some("legacy")
+        );
+        assert!(!root.join("token").exists());
+
+        store.write("aria", "fresh").expect("written");
+        store.write("bram", "other").expect("written");
+        assert_eq!(
+            store.read("aria").expect("readable").as_deref(),
+            Some("fresh")
+        );
+        assert_eq!(store.list().expect("listed"), ["aria", "bram"]);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| {
+                std::fs::metadata(path)
+                    .expect("metadata")
+                    .permissions()
+                    .mode()
+                    & 0o777
+            };
+            assert_eq!(mode(&root.join("tokens")), 0o700);
+            assert_eq!(mode(&root.join("tokens").join("aria")), 0o600);
+        }
+
+        assert!(store.forget("bram").expect("forgotten"));
+        assert!(!store.forget("bram").expect("already gone"));
+        assert!(matches!(
+            store.forget("../token"),
+            Err(TokenError::InvalidUsername(_))
+        ));
+        assert_eq!(store.list().expect("listed"), ["aria"]);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn encrypted_tokens_need_the_passphrase() {
+        let root = temp_root("tokens-sealed");
+        let passphrase = Some(Passphrase("correct horse".to_owned()));
+        let store = TokenStore::new(root.clone(), passphrase);
+
+        store.write("aria", "s3cret").expect("written");
+        let contents = std::fs::read_to_string(root.join("tokens").join("aria")).expect("file");
+        assert!(contents.starts_with(ENCRYPTED_PREFIX));
+        assert!(!contents.contains("s3cret"));
+        assert_eq!(
+            store.read("aria").expect("decrypted").as_deref(),
+            Some("s3cret")
+        );
+
+        let locked = TokenStore::new(root.clone(), None);
+        assert!(matches!(
+            locked.read("aria"),
+            Err(TokenError::Locked { .. })
+        ));
+        let wrong = TokenStore::new(root.clone(), Some(Passphrase("wrong".to_owned())));
+        assert!(matches!(
+            wrong.read("aria"),
+            Err(TokenError::Undecryptable { .. })
+        ));
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -2,21 +2,48 @@
 //!
 //! Each tool is a thin pass-through to the game server. The handler
 //! sends the command over WebSocket, awaits the response, drains
-//! any buffered push events, and returns the combined result.
-
-use std::sync::Arc;
-
-use rmcp::handler::server::tool::ToolRouter;
+//! any buffered push events, and returns the combined result, in the
+//! character session the call names or the active one (see
+//! [`crate::sessions`]).
+//!
+//! Besides the built-in tools below, tools described by the server's
+//! manifest are registered at runtime (see [`crate::manifest`]), and
+//! the latest game state is published as resources (see
+//! [`crate::resources`]). Built-in prompts live in [`crate::prompts`].
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, PoisonError};
+use std::time::Duration;
+
+use rmcp::handler::server::prompt::PromptContext;
+use rmcp::handler::server::router::prompt::PromptRouter;
+use rmcp::handler::server::tool::{parse_json_object, IntoCallToolResult, ToolRouter};
 use rmcp::handler::server::wrapper::Parameters;
-use rmcp::model::{CallToolResult, Content, ServerCapabilities, ServerInfo};
-use rmcp::{tool, tool_handler, tool_router, ServerHandler};
+use rmcp::model::{
+    CallToolRequestParams, CallToolResult, Content, GetPromptRequestParams, GetPromptResult,
+    JsonObject, ListPromptsResult, ListResourcesResult, ListToolsResult, PaginatedRequestParams,
+    ReadResourceRequestParams, ReadResourceResult, ResourceContents, ServerCapabilities,
+    ServerInfo, SetLevelRequestParams, SubscribeRequestParams, Tool, UnsubscribeRequestParams,
+};
+use rmcp::service::{NotificationContext, RequestContext};
+use rmcp::{tool, tool_router, RoleServer, ServerHandler};
 use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tokio::sync::Mutex;
 
-use crate::connection::{ConnectionError, GameConnection};
-use crate::events::EventBuffer;
+use crate::aliases::{Alias, AliasStep};
+use crate::connection::{ConnectionError, ConnectionOptions, ConnectionState};
+use crate::filter::{EventFilter, Filtered, Verbosity};
+use crate::manifest::{DynamicTools, MANIFEST_ACTION};
+use crate::notify::{forward_urgent_events, Peers, UrgentEvents};
+use crate::observer::{mirror_events, Observer};
+use crate::protocol::{ConnectResult, PushEvent, ResponseBody, RotateTokenResult, ToolManifest};
+use crate::recorder::SessionRecorder;
+use crate::resources::{self, GameResource, GameState, Subscriptions};
+use crate::sessions::{self, Session, Sessions};
+use crate::tokens::{validate_username, Passphrase, TokenStore};
+use crate::triggers::{run_triggers, TriggerRule};
 
 // ---------------------------------------------------------------------------
 // Parameter types
@@ -25,10 +52,45 @@ use crate::events::EventBuffer;
 /// Parameters for authenticating and joining the game world.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ConnectParams {
-    /// Player username.
+    /// Player username. Defaults to the configured username.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Authentication token. Defaults to the token saved for this username.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+/// Parameters for deleting an account's saved token.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AccountForgetParams {
+    /// Username whose saved token to delete.
     pub username: String,
-    /// Authentication token.
-    pub token: String,
+}
+
+/// Parameters for changing the active session.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SessionSwitchParams {
+    /// Character whose session tools should act on from now on.
+    pub name: String,
+}
+
+/// Parameters for changing a session's event filter.
+///
+/// Omitted fields keep their current value; pass an empty list to clear one.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SetEventFilterParams {
+    /// "minimal" (only combat, death, tells, invites, level-ups),
+    /// "normal" (all but arrivals, departures and shouts) or "everything"
+    /// (the default).
+    pub verbosity: Option<Verbosity>,
+    /// Event types to hide (e.g. "say").
+    pub hide_events: Option<Vec<String>>,
+    /// Chat channels to hide (e.g. "trade").
+    pub hide_channels: Option<Vec<String>>,
+    /// Players whose messages and movements to hide.
+    pub hide_players: Option<Vec<String>>,
+    /// Rooms (by name or ID) whose events to hide.
+    pub hide_rooms: Option<Vec<String>>,
 }
 
 /// Parameters for observing the room or examining a target.
@@ -45,6 +107,13 @@ pub struct MoveParams {
     pub direction: String,
 }
 
+/// Parameters for travelling to a previously explored room.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TravelToParams {
+    /// Destination room, by ID or by name (case-insensitive).
+    pub destination: String,
+}
+
 /// Parameters for attacking a target.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct AttackParams {
@@ -256,35 +325,183 @@ pub struct GuildDepositParams {
     pub amount: u64,
 }
 
+/// A single step of a batch: one tool call.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct BatchStep {
+    /// Tool to call (e.g. "equip", "look").
+    pub tool: String,
+    /// Arguments for the tool, exactly as when calling it directly.
+    pub arguments: Option<serde_json::Map<String, Value>>,
+}
+
+/// Parameters for running several tools in one call.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct BatchParams {
+    /// Steps to run in order.
+    pub steps: Vec<BatchStep>,
+    /// Event types that stop the batch (e.g. "tell", "death").
+    /// Defaults to combat, death, tells and party invites.
+    pub interrupt_on: Option<Vec<String>>,
+}
+
+/// Parameters for removing a trigger.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TriggerRemoveParams {
+    /// ID of the trigger, as shown by `trigger_list`.
+    pub id: u32,
+}
+
+/// Parameters for defining an alias.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AliasDefineParams {
+    /// Alias name.
+    pub name: String,
+    /// What the alias is for.
+    pub description: Option<String>,
+    /// Server actions to send, in order.
+    pub steps: Vec<AliasStep>,
+}
+
+/// Parameters for running an alias.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AliasRunParams {
+    /// Alias name.
+    pub name: String,
+    /// Positional arguments, substituted for `$1`, `$2`, ...
+    pub args: Option<Vec<String>>,
+    /// Named arguments, e.g. {"target": "goblin"} for `$target`.
+    pub vars: Option<BTreeMap<String, String>>,
+}
+
+/// Parameters for sending an arbitrary server action.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RawCommandParams {
+    /// Server action name (e.g. "look", "craft").
+    pub action: String,
+    /// Action parameters, passed through unchanged.
+    pub params: Option<serde_json::Map<String, Value>>,
+}
+
 // ---------------------------------------------------------------------------
 // GameHandler
 // ---------------------------------------------------------------------------
 
+/// Most steps a single `batch` call or alias run may send.
+const MAX_BATCH_STEPS: usize = 25;
+
+/// Argument naming the session a tool call acts on.
+const SESSION_ARG: &str = "session";
+
+/// Tools that manage sessions or accounts rather than act in one session,
+/// and so take no `session` argument.
+const SESSIONLESS_TOOLS: &[&str] = &[
+    "connect",
+    "session_list",
+    "session_switch",
+    "accounts_list",
+    "account_forget",
+];
+
+/// Optional behaviour toggled from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct HandlerOptions {
+    /// Expose the `raw_command` passthrough tool.
+    pub allow_raw: bool,
+    /// Push these event types to the MCP client as soon as they arrive.
+    ///
+    /// `None` leaves every event buffered until the next tool call.
+    pub urgent_events: Option<UrgentEvents>,
+    /// Write session transcripts under `<token_path>/sessions`.
+    pub record: bool,
+    /// Character `connect` logs in as when no username is given.
+    pub default_username: Option<String>,
+    /// How long to wait for the server to answer a command, if not the
+    /// connection's default.
+    pub response_timeout: Option<Duration>,
+    /// Encrypt saved tokens with this passphrase.
+    pub token_passphrase: Option<Passphrase>,
+    /// Mirror commands, responses and events to local observers.
+    pub observer: Option<Observer>,
+    /// Event filter new sessions start with.
+    pub event_filter: EventFilter,
+    /// Event filters for particular characters, instead of `event_filter`.
+    pub event_filters: BTreeMap<String, EventFilter>,
+}
+
 /// MCP server handler bridging Claude Code to the game server.
 ///
-/// Holds the WebSocket connection and event buffer behind a mutex
-/// so that the rmcp framework can clone and share this handler
-/// across async tasks.
+/// Holds one session per logged-in character in a shared registry so
+/// that the rmcp framework can clone and share this handler across
+/// async tasks.
 #[derive(Clone, Debug)]
 pub struct GameHandler {
-    connection: Arc<Mutex<GameConnection>>,
-    events: Arc<Mutex<EventBuffer>>,
+    sessions: Sessions,
+    connection_options: ConnectionOptions,
     server_url: String,
     token_path: String,
     tool_router: ToolRouter<Self>,
+    prompt_router: PromptRouter<Self>,
+    dynamic_tools: DynamicTools,
+    state: GameState,
+    peers: Peers,
+    /// Resources the client served by this handler subscribed to.
+    subscriptions: Subscriptions,
+    urgent_events: Option<UrgentEvents>,
+    /// Where transcripts go, with `--record`.
+    recordings: Option<std::path::PathBuf>,
+    default_username: Option<String>,
+    tokens: TokenStore,
+    observer: Option<Observer>,
+    event_filter: EventFilter,
+    event_filters: BTreeMap<String, EventFilter>,
 }
 
 #[tool_router]
 impl GameHandler {
     /// Creates a new handler targeting `server_url` with token storage at `token_path`.
-    pub fn new(server_url: String, token_path: String) -> Self {
-        let (event_tx, event_rx) = tokio::sync::mpsc::unbounded_channel();
+    ///
+    /// The `raw_command` tool is only exposed when `options.allow_raw` is set.
+    pub fn new(server_url: String, token_path: String, options: HandlerOptions) -> Self {
+        let base = std::path::PathBuf::from(expand_tilde(&token_path));
+        let recordings = options.record.then(|| base.join("sessions"));
+        let tokens = TokenStore::new(base, options.token_passphrase);
+        let mut connection_options = ConnectionOptions::default();
+        if let Some(timeout) = options.response_timeout {
+            connection_options.response_timeout = timeout;
+        }
+        let mut tool_router = Self::tool_router();
+        if !options.allow_raw {
+            tool_router.remove_route("raw_command");
+        }
         Self {
-            connection: Arc::new(Mutex::new(GameConnection::new(event_tx))),
-            events: Arc::new(Mutex::new(EventBuffer::new(event_rx))),
+            sessions: Sessions::default(),
+            connection_options,
             server_url,
             token_path,
-            tool_router: Self::tool_router(),
+            tool_router,
+            prompt_router: Self::prompt_router(),
+            dynamic_tools: DynamicTools::default(),
+            state: GameState::default(),
+            peers: Peers::default(),
+            subscriptions: Subscriptions::default(),
+            urgent_events: options.urgent_events,
+            recordings,
+            default_username: options.default_username,
+            tokens,
+            observer: options.observer,
+            event_filter: options.event_filter,
+            event_filters: options.event_filters,
+        }
+    }
+
+    /// Returns a handler for one more MCP client.
+    ///
+    /// It shares every session and cache with this one but keeps its own
+    /// resource subscriptions.
+    pub fn for_client(&self) -> Self {
+        Self {
+            subscriptions: Subscriptions::default(),
+            ..self.clone()
         }
     }
 
@@ -292,79 +509,324 @@ impl GameHandler {
 
     /// Connect to the game world with username and token. Returns initial room state.
     #[tool(
-        description = "Connect to the game world with username and token. Returns initial room state. If new_account is true, the server created a fresh Warrior character automatically — there is no class selection. Just show the token and start playing."
+        description = "Connect to the game world with username and token. Returns initial room state. Omit the token to use the one saved for this username (see accounts_list). Connecting as another character opens a second session alongside the first and makes it the active one (see session_list). If new_account is true, the server created a fresh Warrior character automatically — there is no class selection. Just show the token and start playing."
     )]
     async fn connect(
         &self,
         Parameters(params): Parameters<ConnectParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
-        let mut conn = self.connection.lock().await;
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        let username = params
+            .username
+            .or_else(|| self.default_username.clone())
+            .ok_or_else(|| {
+                rmcp::ErrorData::invalid_params(
+                    "`username` is required: no default username is configured",
+                    None,
+                )
+            })?;
+
+        // Use provided token, or try reading from per-username token file
+        let token = match params.token.filter(|token| !token.is_empty()) {
+            Some(token) => token,
+            None => self
+                .tokens
+                .read(&username)
+                .map_err(|err| rmcp::ErrorData::invalid_params(err.to_string(), None))?
+                .unwrap_or_default(),
+        };
+
+        // Also refuses usernames that cannot name a per-character file.
+        let maps = self.character_file("maps", &username)?;
+        let triggers = self.character_file("triggers", &username)?;
+        let aliases = self.character_file("aliases", &username)?;
+
+        let (session, opened) = match self.sessions.get(&username) {
+            Some(session) => (session, false),
+            None => (self.open_session(&username).await, true),
+        };
+        let mut conn = session.connection.lock().await;
+        // Kept so a refused re-login can put the old one back.
+        let previous_token = conn.token().await;
 
         if conn.is_connected() {
             conn.disconnect().await;
         }
 
-        conn.connect(&self.server_url)
-            .await
-            .map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))?;
-
-        // Use provided token, or try reading from per-username token file
-        let token = if params.token.is_empty() {
-            self.read_token_for(&params.username)
-        } else {
-            params.token
-        };
+        if let Err(err) = conn.connect(&self.server_url).await {
+            drop(conn);
+            if opened {
+                self.close_session(&session).await;
+            }
+            return Err(rmcp::ErrorData::internal_error(err.to_string(), None));
+        }
 
-        let username = params.username.clone();
         let auth_params = serde_json::json!({
             "username": username,
             "token": token,
         });
 
         drop(conn);
-        let result = self.send_and_drain("connect", auth_params).await?;
+        if opened {
+            session.world.open(maps);
+            session.triggers.open(triggers);
+            session.aliases.open(aliases);
+        }
+        if let Some(recorder) = &session.recorder {
+            recorder.start(&username);
+        }
+        let mut body = match self.request_on(&session, "connect", auth_params).await {
+            Ok(body) => body,
+            Err(err) => {
+                if opened {
+                    self.close_session(&session).await;
+                } else {
+                    self.restore_login(&session, previous_token).await;
+                }
+                return Err(err);
+            }
+        };
 
         // If server returned a new account token, save it to disk and strip
         // new_account flag from the response so Claude doesn't try to run
         // a character creation flow.
-        let mut final_result = result;
-        if let Some(content) = final_result.content.first() {
-            if let Some(text_content) = content.as_text() {
-                if let Ok(mut parsed) = serde_json::from_str::<Value>(&text_content.text) {
-                    if let Some(result_obj) = parsed.get_mut("result") {
-                        if let Some(obj) = result_obj.as_object_mut() {
-                            let is_new =
-                                obj.get("new_account").and_then(Value::as_bool) == Some(true);
-                            if is_new {
-                                if let Some(new_token) =
-                                    obj.get("token").and_then(|v| v.as_str().map(String::from))
-                                {
-                                    self.write_token_for(&username, &new_token);
-                                }
-                            }
-                            // Remove new_account flag so Claude sees a clean room response
-                            obj.remove("new_account");
-                        }
-                    }
-                    // Rebuild the result with cleaned JSON
-                    final_result = CallToolResult::success(vec![Content::text(
-                        serde_json::to_string(&parsed).unwrap_or_default(),
-                    )]);
-                }
+        let mut session_token = token;
+        let connect = body.parse::<ConnectResult>().unwrap_or_default();
+        if connect.new_account {
+            if let Some(new_token) = connect.token {
+                self.write_token_for(&username, &new_token);
+                session_token = new_token;
             }
         }
 
-        Ok(final_result)
+        if let ResponseBody::Success(result) = &mut body {
+            // Remove new_account flag so Claude sees a clean room response
+            result.remove("new_account");
+
+            // Only a successful login changes which character tools act as.
+            self.sessions.activate(&username);
+            self.forget_game_state().await;
+
+            // Remember the credentials so a dropped socket can resume the session.
+            session
+                .connection
+                .lock()
+                .await
+                .set_credentials(username, session_token)
+                .await;
+
+            self.refresh_manifest().await;
+        } else if opened {
+            // A refused login leaves no session behind, as a failed socket does.
+            self.close_session(&session).await;
+        } else {
+            self.restore_login(&session, previous_token).await;
+        }
+
+        Ok(self.respond_in(&session, body).await)
     }
 
     /// Disconnect from the game world. Saves your character.
-    #[tool(description = "Disconnect from the game world. Saves your character.")]
-    async fn disconnect(&self) -> Result<CallToolResult, rmcp::ErrorData> {
-        let mut conn = self.connection.lock().await;
-        conn.disconnect().await;
-        Ok(CallToolResult::success(vec![Content::text(
-            r#"{"status":"ok","message":"Disconnected from game server"}"#,
-        )]))
+    #[tool(
+        description = "Disconnect from the game world. Saves your character. Only the active session (or the one named by `session`) is closed; another open session becomes active."
+    )]
+    async fn disconnect(&self) -> Result<ToolOutput, rmcp::ErrorData> {
+        if let Ok(session) = self.session() {
+            self.close_session(&session).await;
+        }
+        let result = serde_json::json!({
+            "status": "ok",
+            "message": "Disconnected from game server",
+            "active_session": self.sessions.active_name(),
+        });
+        Ok(local_result(result))
+    }
+
+    /// Show the health of the link to the game server.
+    #[tool(
+        description = "Show the health of the link to the game server: connection state (connecting, connected, reconnecting, resuming, closed) and the rolling round-trip latency from keepalive pings."
+    )]
+    async fn connection_status(&self) -> Result<ToolOutput, rmcp::ErrorData> {
+        let status = match self.session() {
+            Ok(session) => serde_json::json!({
+                "server": self.server_url,
+                "session": session.name,
+                "state": session.link.state(),
+                "latency": session.link.latency(),
+            }),
+            Err(_) => serde_json::json!({
+                "server": self.server_url,
+                "session": null,
+                "state": ConnectionState::Closed,
+            }),
+        };
+        Ok(local_result(status))
+    }
+
+    // -- Session tools ------------------------------------------------------
+
+    /// List the characters logged in at the same time.
+    #[tool(
+        description = "List the open character sessions, marking the active one that tools act on by default. Any tool except connect and the session/account tools takes a `session` argument to act as another open character instead."
+    )]
+    async fn session_list(&self) -> Result<ToolOutput, rmcp::ErrorData> {
+        let active = self.sessions.active_name();
+        let sessions: Vec<_> = self
+            .sessions
+            .all()
+            .into_iter()
+            .map(|session| {
+                serde_json::json!({
+                    "name": session.name,
+                    "active": active.as_deref() == Some(session.name.as_str()),
+                    "state": session.link.state(),
+                })
+            })
+            .collect();
+        let result = serde_json::json!({ "sessions": sessions, "active": active });
+        Ok(local_result(result))
+    }
+
+    /// Change which session tools act on by default.
+    #[tool(
+        description = "Make another open session the active one, so tools act as that character by default. Returns the events it received since its last tool call."
+    )]
+    async fn session_switch(
+        &self,
+        Parameters(params): Parameters<SessionSwitchParams>,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        if !self.sessions.activate(&params.name) {
+            return Err(rmcp::ErrorData::invalid_params(
+                format!("no open session for {}", params.name),
+                None,
+            ));
+        }
+        tracing::info!(session = %params.name, "session.switched");
+        self.forget_game_state().await;
+        let mut result = serde_json::Map::new();
+        result.insert("active".to_owned(), params.name.into());
+        Ok(self.respond(ResponseBody::Success(result)).await)
+    }
+
+    /// Choose which events tool responses report in full.
+    #[tool(
+        description = "Choose which buffered events this session's tool responses report in full; the rest are only counted by type under events_hidden. Set a verbosity (minimal, normal, or the default everything) and/or hide events by type, chat channel, player or room. Omitted fields are unchanged, so call with no arguments to see the current filter."
+    )]
+    async fn set_event_filter(
+        &self,
+        Parameters(params): Parameters<SetEventFilterParams>,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        let session = self.session()?;
+        let filter = {
+            let mut filter = session
+                .filter
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(verbosity) = params.verbosity {
+                filter.verbosity = verbosity;
+            }
+            if let Some(hide_events) = params.hide_events {
+                filter.hide_events = hide_events;
+            }
+            if let Some(hide_channels) = params.hide_channels {
+                filter.hide_channels = hide_channels;
+            }
+            if let Some(hide_players) = params.hide_players {
+                filter.hide_players = hide_players;
+            }
+            if let Some(hide_rooms) = params.hide_rooms {
+                filter.hide_rooms = hide_rooms;
+            }
+            filter.clone()
+        };
+        let result = serde_json::json!({ "session": session.name, "event_filter": filter });
+        Ok(local_result(result))
+    }
+
+    // -- Account tools ------------------------------------------------------
+
+    /// List the accounts with a saved token.
+    #[tool(
+        description = "List the accounts with a saved token, marking the active one and any with an open session. Connect with just a username to log in as any of them."
+    )]
+    async fn accounts_list(&self) -> Result<ToolOutput, rmcp::ErrorData> {
+        let usernames = self
+            .tokens
+            .list()
+            .map_err(|err| rmcp::ErrorData::internal_error(err.to_string(), None))?;
+        let active = self.sessions.active_name();
+        let accounts: Vec<_> = usernames
+            .into_iter()
+            .map(|username| {
+                let is_active = active.as_deref() == Some(username.as_str());
+                let open = self.sessions.get(&username).is_some();
+                serde_json::json!({ "username": username, "active": is_active, "open": open })
+            })
+            .collect();
+        let result = serde_json::json!({
+            "accounts": accounts,
+            "default_username": self.default_username,
+        });
+        Ok(local_result(result))
+    }
+
+    /// Delete an account's saved token.
+    #[tool(
+        description = "Delete the token saved for an account. Without it the account can only be used by passing its token to connect again."
+    )]
+    async fn account_forget(
+        &self,
+        Parameters(params): Parameters<AccountForgetParams>,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        let forgotten = self
+            .tokens
+            .forget(&params.username)
+            .map_err(|err| rmcp::ErrorData::invalid_params(err.to_string(), None))?;
+        if !forgotten {
+            return Err(rmcp::ErrorData::invalid_params(
+                format!("no saved token for {}", params.username),
+                None,
+            ));
+        }
+        tracing::info!(username = %params.username, "token.forgotten");
+        let result = serde_json::json!({
+            "status": "ok",
+            "message": format!("Forgot the saved token for {}", params.username),
+        });
+        Ok(local_result(result))
+    }
+
+    /// Replace the logged-in account's token with a new one.
+    #[tool(
+        description = "Ask the server for a new token for the logged-in account and save it, invalidating the old one. Use if the token may have leaked."
+    )]
+    async fn account_rotate_token(&self) -> Result<ToolOutput, rmcp::ErrorData> {
+        let session = self.session()?;
+        let Some(username) = session.connection.lock().await.username().await else {
+            return Err(not_connected());
+        };
+        let mut body = self
+            .request_on(&session, "rotate_token", serde_json::json!({}))
+            .await?;
+
+        if let Some(rotated) = body.parse::<RotateTokenResult>() {
+            // The old token is already dead, so only hide the new one once
+            // it is safely on disk.
+            let saved = self.write_token_for(&username, &rotated.token);
+            session
+                .connection
+                .lock()
+                .await
+                .set_credentials(username, rotated.token)
+                .await;
+            if let ResponseBody::Success(result) = &mut body {
+                if saved {
+                    result.remove("token");
+                }
+                result.insert("saved".to_owned(), Value::Bool(saved));
+            }
+        }
+
+        Ok(self.respond_in(&session, body).await)
     }
 
     // -- Navigation tools ---------------------------------------------------
@@ -376,7 +838,7 @@ impl GameHandler {
     async fn look(
         &self,
         Parameters(params): Parameters<LookParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         let mut p = serde_json::Map::new();
         if let Some(target) = params.target {
             p.insert("target".to_owned(), Value::String(target));
@@ -391,17 +853,92 @@ impl GameHandler {
     async fn move_direction(
         &self,
         Parameters(params): Parameters<MoveParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("move", serde_json::json!({ "direction": params.direction }))
             .await
     }
 
     /// Display a simple ASCII map of nearby explored rooms.
     #[tool(description = "Display a simple ASCII map of nearby explored rooms.")]
-    async fn map(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn map(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("map", serde_json::json!({})).await
     }
 
+    /// Walk to a previously explored room along the shortest known route.
+    #[tool(
+        description = "Walk to a previously explored room (by name or ID) along the shortest known route, one move at a time. Stops early on combat, death, tells or party invites, or if a move fails. Only rooms and exits you have already walked are known."
+    )]
+    async fn travel_to(
+        &self,
+        Parameters(params): Parameters<TravelToParams>,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        let session = self.session()?;
+        let route = session
+            .world
+            .route_to(&params.destination)
+            .map_err(|e| rmcp::ErrorData::invalid_params(e.to_string(), None))?;
+
+        let mut events = Vec::new();
+        let mut moves = Vec::new();
+        let mut room = None;
+        let mut stopped = None;
+        for step in &route {
+            let body = match self
+                .request_on(
+                    &session,
+                    "move",
+                    serde_json::json!({ "direction": step.direction }),
+                )
+                .await
+            {
+                Ok(body) => body,
+                Err(err) => {
+                    stopped = Some(err.message.into_owned());
+                    break;
+                }
+            };
+            let result = match body {
+                ResponseBody::Success(result) => result,
+                ResponseBody::Error { error } => {
+                    stopped = Some(format!(
+                        "move {} refused: {}",
+                        step.direction, error.message
+                    ));
+                    break;
+                }
+            };
+            moves.push(step.direction.clone());
+            room = Some(result);
+
+            let drained = session.events.lock().await.drain();
+            let interrupt = drained.iter().find(|event| event.is_interrupting());
+            if let Some(event) = interrupt {
+                stopped = Some(format!("interrupted by {} event", event.kind()));
+            }
+            events.extend(drained);
+            if stopped.is_some() {
+                break;
+            }
+            if session.world.current().as_deref() != Some(step.room.as_str()) {
+                stopped = Some(format!(
+                    "expected to reach {} but ended up elsewhere",
+                    step.room
+                ));
+                break;
+            }
+        }
+
+        let mut summary = serde_json::Map::new();
+        summary.insert("destination".to_owned(), params.destination.into());
+        summary.insert("arrived".to_owned(), stopped.is_none().into());
+        summary.insert("moves".to_owned(), moves.into());
+        summary.insert("stopped".to_owned(), stopped.into());
+        summary.insert("room".to_owned(), room.map_or(Value::Null, Value::Object));
+        Ok(self
+            .respond_with_events(&session, ResponseBody::Success(summary), events)
+            .await)
+    }
+
     // -- Combat tools -------------------------------------------------------
 
     /// Attack a target with your equipped or specified weapon.
@@ -411,7 +948,7 @@ impl GameHandler {
     async fn attack(
         &self,
         Parameters(params): Parameters<AttackParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         let mut p = serde_json::json!({ "target": params.target });
         if let Some(weapon) = params.weapon {
             p["weapon"] = Value::String(weapon);
@@ -426,7 +963,7 @@ impl GameHandler {
     async fn use_ability(
         &self,
         Parameters(params): Parameters<UseAbilityParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         let mut p = serde_json::json!({ "ability": params.ability });
         if let Some(target) = params.target {
             p["target"] = Value::String(target);
@@ -436,7 +973,7 @@ impl GameHandler {
 
     /// Attempt to flee from combat.
     #[tool(description = "Attempt to flee from combat. May fail depending on circumstances.")]
-    async fn flee(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn flee(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("flee", serde_json::json!({})).await
     }
 
@@ -444,7 +981,7 @@ impl GameHandler {
     #[tool(
         description = "Show your full status: HP, mana, level, XP, eq/balance, active effects, and location."
     )]
-    async fn status(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn status(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("status", serde_json::json!({})).await
     }
 
@@ -452,7 +989,7 @@ impl GameHandler {
 
     /// List all items in your inventory with their stats.
     #[tool(description = "List all items in your inventory with their stats.")]
-    async fn inventory(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn inventory(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("inventory", serde_json::json!({}))
             .await
     }
@@ -462,7 +999,7 @@ impl GameHandler {
     async fn get_item(
         &self,
         Parameters(params): Parameters<GetItemParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("get", serde_json::json!({ "item": params.item }))
             .await
     }
@@ -472,7 +1009,7 @@ impl GameHandler {
     async fn drop_item(
         &self,
         Parameters(params): Parameters<DropItemParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("drop", serde_json::json!({ "item": params.item }))
             .await
     }
@@ -484,7 +1021,7 @@ impl GameHandler {
     async fn equip(
         &self,
         Parameters(params): Parameters<EquipParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         let mut p = serde_json::json!({ "item": params.item });
         if let Some(slot) = params.slot {
             p["slot"] = Value::String(slot);
@@ -497,7 +1034,7 @@ impl GameHandler {
     async fn use_item(
         &self,
         Parameters(params): Parameters<UseItemParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         let mut p = serde_json::json!({ "item": params.item });
         if let Some(target) = params.target {
             p["target"] = Value::String(target);
@@ -514,7 +1051,7 @@ impl GameHandler {
     async fn say(
         &self,
         Parameters(params): Parameters<SayParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("say", serde_json::json!({ "message": params.message }))
             .await
     }
@@ -524,7 +1061,7 @@ impl GameHandler {
     async fn tell(
         &self,
         Parameters(params): Parameters<TellParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "tell",
             serde_json::json!({ "player": params.player, "message": params.message }),
@@ -537,7 +1074,7 @@ impl GameHandler {
     async fn shout(
         &self,
         Parameters(params): Parameters<ShoutParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("shout", serde_json::json!({ "message": params.message }))
             .await
     }
@@ -547,14 +1084,14 @@ impl GameHandler {
     async fn emote(
         &self,
         Parameters(params): Parameters<EmoteParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("emote", serde_json::json!({ "action": params.action }))
             .await
     }
 
     /// List all online players with their level and location.
     #[tool(description = "List all online players with their level and location.")]
-    async fn who(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn who(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("who", serde_json::json!({})).await
     }
 
@@ -563,7 +1100,7 @@ impl GameHandler {
     async fn channel(
         &self,
         Parameters(params): Parameters<ChannelParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "channel",
             serde_json::json!({ "name": params.name, "message": params.message }),
@@ -578,7 +1115,7 @@ impl GameHandler {
     async fn talk(
         &self,
         Parameters(params): Parameters<TalkParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("talk", serde_json::json!({ "target": params.target }))
             .await
     }
@@ -590,7 +1127,7 @@ impl GameHandler {
     async fn dialogue_select(
         &self,
         Parameters(params): Parameters<DialogueSelectParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "dialogue_select",
             serde_json::json!({ "npc": params.npc, "option": params.option }),
@@ -605,7 +1142,7 @@ impl GameHandler {
     async fn party_invite(
         &self,
         Parameters(params): Parameters<PartyInviteParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "party_invite",
             serde_json::json!({ "player": params.player }),
@@ -615,14 +1152,14 @@ impl GameHandler {
 
     /// Accept a pending party invitation.
     #[tool(description = "Accept a pending party invitation.")]
-    async fn party_accept(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn party_accept(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("party_accept", serde_json::json!({}))
             .await
     }
 
     /// Leave your current party.
     #[tool(description = "Leave your current party.")]
-    async fn party_leave(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn party_leave(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("party_leave", serde_json::json!({}))
             .await
     }
@@ -632,14 +1169,14 @@ impl GameHandler {
     async fn party_kick(
         &self,
         Parameters(params): Parameters<PartyKickParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("party_kick", serde_json::json!({ "player": params.player }))
             .await
     }
 
     /// Show party members with their HP and location.
     #[tool(description = "Show party members with their HP and location.")]
-    async fn party_list(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn party_list(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("party_list", serde_json::json!({}))
             .await
     }
@@ -651,7 +1188,7 @@ impl GameHandler {
     async fn matchmake(
         &self,
         Parameters(params): Parameters<MatchmakeParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         let mut p = serde_json::Map::new();
         if let Some(role) = params.role {
             p.insert("role".to_owned(), Value::String(role));
@@ -671,7 +1208,7 @@ impl GameHandler {
     async fn companion(
         &self,
         Parameters(params): Parameters<CompanionParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "companion",
             serde_json::json!({ "command": params.command }),
@@ -681,7 +1218,7 @@ impl GameHandler {
 
     /// Check your AI companion's HP, equipment, and current behavior.
     #[tool(description = "Check your AI companion's HP, equipment, and current behavior.")]
-    async fn companion_status(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn companion_status(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("companion_status", serde_json::json!({}))
             .await
     }
@@ -690,7 +1227,7 @@ impl GameHandler {
     #[tool(
         description = "Read your companion's core memories — milestones and notes from your journey together."
     )]
-    async fn companion_memory(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn companion_memory(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("companion_memory", serde_json::json!({}))
             .await
     }
@@ -702,7 +1239,7 @@ impl GameHandler {
     async fn companion_memory_write(
         &self,
         Parameters(params): Parameters<CompanionMemoryWriteParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         let mut p = serde_json::json!({ "text": params.text });
         if let Some(tag) = params.tag {
             p["tag"] = Value::String(tag);
@@ -716,21 +1253,21 @@ impl GameHandler {
     #[tool(
         description = "View your full character sheet: class, level, stats, abilities, and equipment."
     )]
-    async fn character_info(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn character_info(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("character_info", serde_json::json!({}))
             .await
     }
 
     /// List all your available abilities with descriptions and cooldowns.
     #[tool(description = "List all your available abilities with descriptions and cooldowns.")]
-    async fn abilities(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn abilities(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("abilities", serde_json::json!({}))
             .await
     }
 
     /// Show your active and completed quests.
     #[tool(description = "Show your active and completed quests.")]
-    async fn quests(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn quests(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("quests", serde_json::json!({})).await
     }
 
@@ -741,7 +1278,7 @@ impl GameHandler {
     async fn leaderboard(
         &self,
         Parameters(params): Parameters<LeaderboardParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         let board_type = params.board_type.unwrap_or_else(|| "level".to_owned());
         self.send_and_drain(
             "leaderboard",
@@ -755,7 +1292,7 @@ impl GameHandler {
     async fn suggest_description(
         &self,
         Parameters(params): Parameters<SuggestDescriptionParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "suggest_description",
             serde_json::json!({ "context": params.context }),
@@ -770,7 +1307,7 @@ impl GameHandler {
     async fn buy(
         &self,
         Parameters(params): Parameters<BuyParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "buy",
             serde_json::json!({ "shop_id": params.shop_id, "item": params.item }),
@@ -783,7 +1320,7 @@ impl GameHandler {
     async fn sell(
         &self,
         Parameters(params): Parameters<SellParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "sell",
             serde_json::json!({ "shop_id": params.shop_id, "item": params.item }),
@@ -798,7 +1335,7 @@ impl GameHandler {
     async fn accept_quest(
         &self,
         Parameters(params): Parameters<AcceptQuestParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "accept_quest",
             serde_json::json!({ "quest_id": params.quest_id }),
@@ -811,7 +1348,7 @@ impl GameHandler {
     async fn complete_quest(
         &self,
         Parameters(params): Parameters<CompleteQuestParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "complete_quest",
             serde_json::json!({ "quest_id": params.quest_id }),
@@ -826,7 +1363,7 @@ impl GameHandler {
     async fn guild_create(
         &self,
         Parameters(params): Parameters<GuildCreateParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("guild_create", serde_json::json!({ "name": params.name }))
             .await
     }
@@ -836,7 +1373,7 @@ impl GameHandler {
     async fn guild_invite(
         &self,
         Parameters(params): Parameters<GuildInviteParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "guild_invite",
             serde_json::json!({ "player": params.player }),
@@ -846,14 +1383,14 @@ impl GameHandler {
 
     /// Leave your current guild.
     #[tool(description = "Leave your current guild.")]
-    async fn guild_leave(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn guild_leave(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("guild_leave", serde_json::json!({}))
             .await
     }
 
     /// View information about your guild.
     #[tool(description = "View your guild's information: members, bank, and rank.")]
-    async fn guild_info(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+    async fn guild_info(&self) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain("guild_info", serde_json::json!({}))
             .await
     }
@@ -863,20 +1400,247 @@ impl GameHandler {
     async fn guild_deposit(
         &self,
         Parameters(params): Parameters<GuildDepositParams>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
         self.send_and_drain(
             "guild_deposit",
             serde_json::json!({ "amount": params.amount }),
         )
         .await
     }
+
+    // -- Automation tools ---------------------------------------------------
+
+    /// Run a sequence of tool calls in one go.
+    #[tool(
+        description = "Run several tools in order in a single call, e.g. pick up loot, equip it, drink a potion and move on. Stops at the first failing step or when an interrupting event (combat, death, tells, party invites by default) arrives. Returns each step's result and all events together."
+    )]
+    async fn batch(
+        &self,
+        Parameters(params): Parameters<BatchParams>,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        if params.steps.len() > MAX_BATCH_STEPS {
+            return Err(rmcp::ErrorData::invalid_params(
+                format!("a batch may have at most {MAX_BATCH_STEPS} steps"),
+                None,
+            ));
+        }
+        if params.steps.iter().any(|step| step.tool == "batch") {
+            return Err(rmcp::ErrorData::invalid_params(
+                "batches cannot be nested",
+                None,
+            ));
+        }
+
+        let interrupts = |event: &PushEvent| match &params.interrupt_on {
+            Some(kinds) => kinds.iter().any(|kind| kind == event.kind()),
+            None => event.is_interrupting(),
+        };
+
+        let mut steps = Vec::new();
+        let mut events = Vec::new();
+        let mut stopped = None;
+        for step in &params.steps {
+            let request = CallToolRequestParams {
+                meta: None,
+                name: step.tool.clone().into(),
+                arguments: step.arguments.clone(),
+                task: None,
+            };
+            let (mut outcome, failed) = match self.run_tool(request).await {
+                Ok(output) => {
+                    let (body, step_events) = output.into_parts();
+                    events.extend(step_events);
+                    body_outcome(body)
+                }
+                Err(err) => (
+                    serde_json::json!({ "error": { "message": err.message } }),
+                    true,
+                ),
+            };
+            outcome["tool"] = Value::String(step.tool.clone());
+            steps.push(outcome);
+
+            if failed {
+                stopped = Some(format!("step {} ({}) failed", steps.len(), step.tool));
+            } else if let Some(event) = events.iter().find(|event| interrupts(event)) {
+                stopped = Some(format!("interrupted by {} event", event.kind()));
+            }
+            if stopped.is_some() {
+                break;
+            }
+        }
+
+        let mut summary = serde_json::Map::new();
+        summary.insert("completed".to_owned(), stopped.is_none().into());
+        summary.insert("steps".to_owned(), steps.into());
+        summary.insert("stopped".to_owned(), stopped.into());
+        let body = ResponseBody::Success(summary);
+        Ok(match self.session() {
+            Ok(session) => self.respond_with_events(&session, body, events).await,
+            // Every step may have been a session tool, or the last one a
+            // disconnect.
+            Err(_) => respond_with(body, events, None),
+        })
+    }
+
+    /// Add a trigger that reacts to game events automatically.
+    #[tool(
+        description = "Add a trigger that sends a server action whenever a matching event arrives, even between tool calls. Match on the event type and/or a dotted path into the event data, e.g. drink a potion when a combat_update has hp/max_hp below 0.3: {\"event\": \"combat_update\", \"path\": \"hp\", \"ratio_of\": \"max_hp\", \"op\": \"lt\", \"value\": 0.3, \"action\": \"use_item\", \"params\": {\"item\": \"healing potion\"}}. Outcomes are reported as trigger_fired events. Saved per character."
+    )]
+    async fn trigger_add(
+        &self,
+        Parameters(rule): Parameters<TriggerRule>,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        let trigger = self
+            .session()?
+            .triggers
+            .add(rule)
+            .map_err(|e| rmcp::ErrorData::invalid_params(e.to_string(), None))?;
+        let result = serde_json::json!({ "added": trigger });
+        Ok(local_result(result))
+    }
+
+    /// List this character's triggers.
+    #[tool(description = "List this character's triggers with their IDs.")]
+    async fn trigger_list(&self) -> Result<ToolOutput, rmcp::ErrorData> {
+        let result = serde_json::json!({ "triggers": self.session()?.triggers.list() });
+        Ok(local_result(result))
+    }
+
+    /// Remove a trigger by ID.
+    #[tool(description = "Remove a trigger by the ID shown in trigger_list.")]
+    async fn trigger_remove(
+        &self,
+        Parameters(params): Parameters<TriggerRemoveParams>,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        if !self.session()?.triggers.remove(params.id) {
+            return Err(rmcp::ErrorData::invalid_params(
+                format!("no trigger with id {}", params.id),
+                None,
+            ));
+        }
+        let result = serde_json::json!({ "status": "ok", "message": "Trigger removed" });
+        Ok(local_result(result))
+    }
+
+    /// Define or replace a command alias.
+    #[tool(
+        description = "Define (or replace) an alias: a named sequence of server actions whose string parameters may use placeholders — $1, $2, ... for positional arguments and $name for named ones ($$ for a literal $). E.g. name \"nuke\", steps [{\"action\": \"use_ability\", \"params\": {\"ability\": \"fireball\", \"target\": \"$1\"}}]. Saved per character."
+    )]
+    async fn alias_define(
+        &self,
+        Parameters(params): Parameters<AliasDefineParams>,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        let alias = Alias {
+            name: params.name,
+            description: params.description,
+            steps: params.steps,
+        };
+        let replaced = self
+            .session()?
+            .aliases
+            .define(alias)
+            .map_err(|e| rmcp::ErrorData::invalid_params(e.to_string(), None))?;
+        let message = if replaced {
+            "Alias replaced"
+        } else {
+            "Alias defined"
+        };
+        let result = serde_json::json!({ "status": "ok", "message": message });
+        Ok(local_result(result))
+    }
+
+    /// Run a command alias.
+    #[tool(
+        description = "Run an alias with positional args ($1, $2, ...) and/or named vars ($target). Sends its actions in order, stopping at the first failure or interrupting event. Returns each step's result and all events together."
+    )]
+    async fn alias_run(
+        &self,
+        Parameters(params): Parameters<AliasRunParams>,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        let session = self.session()?;
+        let steps = session
+            .aliases
+            .get(&params.name)
+            .and_then(|alias| {
+                alias.expand(
+                    &params.args.unwrap_or_default(),
+                    &params.vars.unwrap_or_default(),
+                )
+            })
+            .map_err(|e| rmcp::ErrorData::invalid_params(e.to_string(), None))?;
+        if steps.len() > MAX_BATCH_STEPS {
+            return Err(rmcp::ErrorData::invalid_params(
+                format!("an alias may run at most {MAX_BATCH_STEPS} steps"),
+                None,
+            ));
+        }
+
+        let mut results = Vec::new();
+        let mut events = Vec::new();
+        let mut stopped = None;
+        for step in steps {
+            let (mut outcome, failed) = match self
+                .request_on(&session, &step.action, Value::Object(step.params))
+                .await
+            {
+                Ok(body) => body_outcome(body),
+                // Keep what the earlier steps did.
+                Err(err) => (
+                    serde_json::json!({ "error": { "message": err.message } }),
+                    true,
+                ),
+            };
+            outcome["action"] = Value::String(step.action.clone());
+            results.push(outcome);
+
+            events.extend(session.events.lock().await.drain());
+            if failed {
+                stopped = Some(format!("{} failed", step.action));
+            } else if let Some(event) = events.iter().find(|event| event.is_interrupting()) {
+                stopped = Some(format!("interrupted by {} event", event.kind()));
+            }
+            if stopped.is_some() {
+                break;
+            }
+        }
+
+        let mut summary = serde_json::Map::new();
+        summary.insert("alias".to_owned(), params.name.into());
+        summary.insert("completed".to_owned(), stopped.is_none().into());
+        summary.insert("steps".to_owned(), results.into());
+        summary.insert("stopped".to_owned(), stopped.into());
+        Ok(self
+            .respond_with_events(&session, ResponseBody::Success(summary), events)
+            .await)
+    }
+
+    /// List this character's aliases.
+    #[tool(description = "List this character's aliases and the actions they expand to.")]
+    async fn alias_list(&self) -> Result<ToolOutput, rmcp::ErrorData> {
+        let result = serde_json::json!({ "aliases": self.session()?.aliases.list() });
+        Ok(local_result(result))
+    }
+
+    // -- Debug tools --------------------------------------------------------
+
+    /// Send any server action with arbitrary parameters.
+    #[tool(
+        description = "Send any game server action with arbitrary JSON parameters, bypassing the built-in tools. For testing new server features and debugging the protocol."
+    )]
+    async fn raw_command(
+        &self,
+        Parameters(params): Parameters<RawCommandParams>,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        let p = Value::Object(params.params.unwrap_or_default());
+        self.send_and_drain(&params.action, p).await
+    }
 }
 
 // ---------------------------------------------------------------------------
 // ServerHandler implementation
 // ---------------------------------------------------------------------------
 
-#[tool_handler]
 impl ServerHandler for GameHandler {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
@@ -885,10 +1649,143 @@ impl ServerHandler for GameHandler {
                  and interact in the multiplayer text dungeon."
                     .into(),
             ),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_logging()
+                .enable_prompts()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .build(),
             ..Default::default()
         }
     }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        self.peers.add(context.peer, self.subscriptions.clone());
+    }
+
+    async fn set_level(
+        &self,
+        _request: SetLevelRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), rmcp::ErrorData> {
+        // Only urgent game events are sent as log messages, always at
+        // `alert`, so there is no lower-priority output to filter.
+        Ok(())
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.run_tool(request).await.map(ToolOutput::into_result)
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, rmcp::ErrorData> {
+        let mut tools = self.tool_router.list_all();
+        tools.extend(self.dynamic_tools.list());
+        for tool in &mut tools {
+            if !SESSIONLESS_TOOLS.contains(&tool.name.as_ref()) {
+                add_session_param(tool);
+            }
+        }
+        Ok(ListToolsResult {
+            tools,
+            meta: None,
+            next_cursor: None,
+        })
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, rmcp::ErrorData> {
+        Ok(ListPromptsResult::with_all_items(
+            self.prompt_router.list_all(),
+        ))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, rmcp::ErrorData> {
+        let pcx = PromptContext::new(self, request.name, request.arguments, context);
+        self.prompt_router.get_prompt(pcx).await
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, rmcp::ErrorData> {
+        Ok(ListResourcesResult::with_all_items(
+            GameResource::ALL
+                .into_iter()
+                .map(GameResource::to_resource)
+                .collect(),
+        ))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, rmcp::ErrorData> {
+        let resource = resource_for_uri(&request.uri)?;
+        let value = match self.state.get(resource) {
+            Some(value) => value,
+            // Not cached yet, or invalidated since: ask the server.
+            None => match self
+                .request(resource.action(), serde_json::json!({}))
+                .await?
+            {
+                ResponseBody::Success(result) => Value::Object(result),
+                ResponseBody::Error { error } => {
+                    return Err(rmcp::ErrorData::internal_error(
+                        format!("Server refused to read {}: {}", request.uri, error.message),
+                        None,
+                    ));
+                }
+            },
+        };
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri: request.uri,
+                mime_type: Some("application/json".to_owned()),
+                text: value.to_string(),
+                meta: None,
+            }],
+        })
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), rmcp::ErrorData> {
+        self.subscriptions
+            .subscribe(resource_for_uri(&request.uri)?);
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), rmcp::ErrorData> {
+        self.subscriptions
+            .unsubscribe(resource_for_uri(&request.uri)?);
+        Ok(())
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -898,19 +1795,278 @@ impl ServerHandler for GameHandler {
 impl GameHandler {
     /// Sends a command to the game server, awaits the response, drains
     /// buffered events, and returns the combined JSON as an MCP tool result.
+    /// Runs the tool `request` names, in the session its `session`
+    /// argument selects.
+    async fn run_tool(
+        &self,
+        mut request: CallToolRequestParams,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        let selected = request
+            .arguments
+            .as_mut()
+            .and_then(|arguments| arguments.remove(SESSION_ARG));
+        let arguments = request.arguments.unwrap_or_default();
+        let call = async {
+            if self.tool_router.has_route(&request.name) {
+                return self.dispatch(&request.name, arguments).await;
+            }
+
+            let Some(tool) = self.dynamic_tools.get(&request.name) else {
+                return Err(rmcp::ErrorData::invalid_params("tool not found", None));
+            };
+            self.send_and_drain(tool.action(), Value::Object(arguments))
+                .await
+        };
+
+        match selected {
+            None | Some(Value::Null) => call.await,
+            Some(Value::String(name)) if self.sessions.get(&name).is_some() => {
+                sessions::with_selected(name, call).await
+            }
+            Some(Value::String(name)) => Err(rmcp::ErrorData::invalid_params(
+                format!("no open session for {name} — see session_list"),
+                None,
+            )),
+            Some(_) => Err(rmcp::ErrorData::invalid_params(
+                "`session` must be a character name",
+                None,
+            )),
+        }
+    }
+
+    /// Calls the built-in tool `name` with `arguments`.
+    ///
+    /// Every tool on offer has an arm here; the tool router only lists
+    /// them.
+    async fn dispatch(
+        &self,
+        name: &str,
+        arguments: JsonObject,
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        match name {
+            "connect" => self.connect(params(arguments)?).await,
+            "disconnect" => self.disconnect().await,
+            "connection_status" => self.connection_status().await,
+            "session_list" => self.session_list().await,
+            "session_switch" => self.session_switch(params(arguments)?).await,
+            "set_event_filter" => self.set_event_filter(params(arguments)?).await,
+            "accounts_list" => self.accounts_list().await,
+            "account_forget" => self.account_forget(params(arguments)?).await,
+            "account_rotate_token" => self.account_rotate_token().await,
+            "look" => self.look(params(arguments)?).await,
+            "move_direction" => self.move_direction(params(arguments)?).await,
+            "map" => self.map().await,
+            "travel_to" => self.travel_to(params(arguments)?).await,
+            "attack" => self.attack(params(arguments)?).await,
+            "use_ability" => self.use_ability(params(arguments)?).await,
+            "flee" => self.flee().await,
+            "status" => self.status().await,
+            "inventory" => self.inventory().await,
+            "get_item" => self.get_item(params(arguments)?).await,
+            "drop_item" => self.drop_item(params(arguments)?).await,
+            "equip" => self.equip(params(arguments)?).await,
+            "use_item" => self.use_item(params(arguments)?).await,
+            "say" => self.say(params(arguments)?).await,
+            "tell" => self.tell(params(arguments)?).await,
+            "shout" => self.shout(params(arguments)?).await,
+            "emote" => self.emote(params(arguments)?).await,
+            "who" => self.who().await,
+            "channel" => self.channel(params(arguments)?).await,
+            "talk" => self.talk(params(arguments)?).await,
+            "dialogue_select" => self.dialogue_select(params(arguments)?).await,
+            "party_invite" => self.party_invite(params(arguments)?).await,
+            "party_accept" => self.party_accept().await,
+            "party_leave" => self.party_leave().await,
+            "party_kick" => self.party_kick(params(arguments)?).await,
+            "party_list" => self.party_list().await,
+            "matchmake" => self.matchmake(params(arguments)?).await,
+            "companion" => self.companion(params(arguments)?).await,
+            "companion_status" => self.companion_status().await,
+            "companion_memory" => self.companion_memory().await,
+            "companion_memory_write" => self.companion_memory_write(params(arguments)?).await,
+            "character_info" => self.character_info().await,
+            "abilities" => self.abilities().await,
+            "quests" => self.quests().await,
+            "leaderboard" => self.leaderboard(params(arguments)?).await,
+            "suggest_description" => self.suggest_description(params(arguments)?).await,
+            "buy" => self.buy(params(arguments)?).await,
+            "sell" => self.sell(params(arguments)?).await,
+            "accept_quest" => self.accept_quest(params(arguments)?).await,
+            "complete_quest" => self.complete_quest(params(arguments)?).await,
+            "guild_create" => self.guild_create(params(arguments)?).await,
+            "guild_invite" => self.guild_invite(params(arguments)?).await,
+            "guild_leave" => self.guild_leave().await,
+            "guild_info" => self.guild_info().await,
+            "guild_deposit" => self.guild_deposit(params(arguments)?).await,
+            "batch" => Box::pin(self.batch(params(arguments)?)).await,
+            "trigger_add" => self.trigger_add(params(arguments)?).await,
+            "trigger_list" => self.trigger_list().await,
+            "trigger_remove" => self.trigger_remove(params(arguments)?).await,
+            "alias_define" => self.alias_define(params(arguments)?).await,
+            "alias_run" => self.alias_run(params(arguments)?).await,
+            "alias_list" => self.alias_list().await,
+            "raw_command" => self.raw_command(params(arguments)?).await,
+            _ => Err(rmcp::ErrorData::invalid_params("tool not found", None)),
+        }
+    }
+
     async fn send_and_drain(
         &self,
         action: &str,
         params: Value,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
+    ) -> Result<ToolOutput, rmcp::ErrorData> {
+        let body = self.request(action, params).await?;
+        Ok(self.respond(body).await)
+    }
+
+    /// Returns the session the tool call in progress acts on: the one
+    /// named by its `session` argument, else the active one.
+    fn session(&self) -> Result<Session, rmcp::ErrorData> {
+        sessions::selected()
+            .or_else(|| self.sessions.active_name())
+            .and_then(|name| self.sessions.get(&name))
+            .ok_or_else(not_connected)
+    }
+
+    /// Logs an existing session back in with the token it had before a
+    /// re-login was refused, if it had one.
+    async fn restore_login(&self, session: &Session, token: Option<String>) {
+        let Some(token) = token else {
+            return;
+        };
+        let params = serde_json::json!({ "username": session.name, "token": token });
+        match self.request_on(session, "connect", params).await {
+            Ok(ResponseBody::Success(_)) => {
+                session
+                    .connection
+                    .lock()
+                    .await
+                    .set_credentials(session.name.clone(), token)
+                    .await;
+                tracing::info!(session = %session.name, "session.login.restored");
+            }
+            Ok(ResponseBody::Error { error }) => {
+                tracing::warn!(error.message = %error.message, session = %session.name, "session.login.restore_refused");
+            }
+            Err(err) => {
+                tracing::warn!(error = %err.message, session = %session.name, "session.login.restore_failed");
+            }
+        }
+    }
+
+    /// Registers a new, unconnected session for `username` and starts its
+    /// background tasks.
+    async fn open_session(&self, username: &str) -> Session {
+        let options = ConnectionOptions {
+            recorder: self.recordings.clone().map(SessionRecorder::new),
+            ..self.connection_options.clone()
+        };
+        let filter = self
+            .event_filters
+            .get(username)
+            .unwrap_or(&self.event_filter)
+            .clone();
+        let session = Session::new(username.to_owned(), options, filter);
+        {
+            let conn = session.connection.lock().await;
+            // Trigger actions go through `request_on` like any tool call.
+            let (handler, acting) = (self.clone(), session.clone());
+            session.spawn(run_triggers(
+                conn.subscribe(),
+                session.triggers.clone(),
+                conn.handle(),
+                move |action, params| {
+                    let (handler, session) = (handler.clone(), acting.clone());
+                    async move {
+                        handler
+                            .request_on(&session, &action, params)
+                            .await
+                            .map_err(|err| err.message.into_owned())
+                    }
+                },
+            ));
+            if let Some(observer) = self.observer.clone() {
+                session.spawn(mirror_events(
+                    conn.subscribe(),
+                    observer,
+                    username.to_owned(),
+                ));
+            }
+            let sessions = self.sessions.clone();
+            let name = username.to_owned();
+            session.spawn(resources::track_events(
+                conn.subscribe(),
+                self.state.clone(),
+                self.peers.clone(),
+                move || sessions.active_name().as_deref() == Some(name.as_str()),
+            ));
+            if let Some(urgent) = self.urgent_events.clone() {
+                session.spawn(forward_urgent_events(
+                    conn.subscribe(),
+                    self.peers.clone(),
+                    urgent,
+                    username.to_owned(),
+                ));
+            }
+        }
+        self.sessions.insert(session.clone());
+        tracing::info!(session = %username, "session.opened");
+        session
+    }
+
+    /// Logs `session` out and removes it.
+    async fn close_session(&self, session: &Session) {
+        session.abort_tasks();
+        session.connection.lock().await.disconnect().await;
+        if let Some(recorder) = &session.recorder {
+            recorder.stop();
+        }
+        session.world.close();
+        session.triggers.close();
+        session.aliases.close();
+
+        let was_active = self.sessions.active_name().as_deref() == Some(session.name.as_str());
+        self.sessions.remove(&session.name);
+        tracing::info!(session = %session.name, "session.closed");
+        if was_active {
+            self.forget_game_state().await;
+        }
+        if self.sessions.active_name().is_none() && self.dynamic_tools.clear() {
+            self.notify_tool_list_changed().await;
+        }
+    }
+
+    /// Sends a command in the session the tool call acts on and returns
+    /// its response body, mapping transport failures to MCP errors.
+    async fn request(&self, action: &str, params: Value) -> Result<ResponseBody, rmcp::ErrorData> {
+        self.request_on(&self.session()?, action, params).await
+    }
+
+    /// Like [`Self::request`], in a given session.
+    async fn request_on(
+        &self,
+        session: &Session,
+        action: &str,
+        params: Value,
+    ) -> Result<ResponseBody, rmcp::ErrorData> {
+        if let Some(observer) = &self.observer {
+            observer.command(&session.name, action, &params);
+        }
         let response = {
-            let conn = self.connection.lock().await;
-            conn.send_command(action, params).await
+            let conn = session.connection.lock().await;
+            conn.send_command(action, params.clone()).await
         };
+        if let Some(observer) = &self.observer {
+            match &response {
+                Ok(response) => observer.response(&session.name, action, &response.body),
+                Err(err) => observer.failure(&session.name, action, &err.to_string()),
+            }
+        }
 
         let response = response.map_err(|e| match &e {
-            ConnectionError::NotConnected => rmcp::ErrorData::invalid_request(
-                "Not connected to game server — call `connect` first",
+            ConnectionError::NotConnected => not_connected(),
+            ConnectionError::Disconnected => rmcp::ErrorData::internal_error(
+                "Connection to game server lost — it is reconnecting automatically; retry shortly",
                 None,
             ),
             ConnectionError::Timeout(_) => {
@@ -919,72 +2075,279 @@ impl GameHandler {
             _ => rmcp::ErrorData::internal_error(e.to_string(), None),
         })?;
 
-        let events = {
-            let mut event_buffer = self.events.lock().await;
-            event_buffer.drain()
+        session.world.record(action, &params, &response.body);
+        // Resources describe the active character only.
+        if self.sessions.active_name().as_deref() == Some(session.name.as_str()) {
+            let changed = self.state.record_response(action, &params, &response.body);
+            self.notify_resources_updated(changed).await;
+        }
+        Ok(response.body)
+    }
+
+    /// Fetches the server's tool manifest and registers its tools.
+    ///
+    /// Servers that predate the manifest answer with an error, in which
+    /// case only the built-in tools stay advertised.
+    async fn refresh_manifest(&self) {
+        let manifest = match self.request(MANIFEST_ACTION, serde_json::json!({})).await {
+            Ok(body) => body.parse::<ToolManifest>(),
+            Err(err) => {
+                tracing::debug!(error = %err.message, "manifest.fetch.failed");
+                None
+            }
         };
 
-        let combined = serde_json::json!({
-            "result": response,
-            "events": events,
-        });
+        let changed = if let Some(manifest) = manifest {
+            tracing::info!(tools = manifest.tools.len(), "manifest.loaded");
+            self.dynamic_tools
+                .replace(manifest.tools, |name| self.tool_router.has_route(name))
+        } else {
+            tracing::debug!("manifest.unavailable");
+            self.dynamic_tools.clear()
+        };
 
-        Ok(CallToolResult::success(vec![Content::text(
-            combined.to_string(),
-        )]))
+        if changed {
+            self.notify_tool_list_changed().await;
+        }
     }
 
-    /// Reads the token for a specific username, returning empty string if unavailable.
-    ///
-    /// Tokens are stored per-username at `<token_path>/tokens/<username>`.
-    /// Falls back to the legacy single-file `<token_path>/token` if the
-    /// per-username file doesn't exist (one-time migration path).
-    fn read_token_for(&self, username: &str) -> String {
-        let base = expand_tilde(&self.token_path);
-        let base = std::path::Path::new(&base);
+    /// Tells the MCP clients to re-fetch the tool list.
+    async fn notify_tool_list_changed(&self) {
+        for peer in self.peers.live() {
+            if let Err(err) = peer.notify_tool_list_changed().await {
+                tracing::warn!(error = %err, "mcp.notify.tool_list_changed.failed");
+            }
+        }
+    }
+
+    /// Returns true if `name` is one of the built-in tools on offer.
+    #[cfg(test)]
+    pub(crate) fn has_tool(&self, name: &str) -> bool {
+        self.tool_router.has_route(name)
+    }
+
+    /// Tells the MCP clients which subscribed resources changed.
+    async fn notify_resources_updated(&self, changed: Vec<GameResource>) {
+        resources::notify_updated(&self.peers, changed).await;
+    }
 
-        // Try per-username path first
-        let per_user = base.join("tokens").join(username);
-        if let Ok(contents) = std::fs::read_to_string(&per_user) {
-            return contents.trim().to_owned();
+    /// Drops the cached game state when a session starts or ends.
+    async fn forget_game_state(&self) {
+        let changed = self.state.clear();
+        self.notify_resources_updated(changed).await;
+    }
+
+    /// Drains the events buffered in the session the tool call acts on
+    /// and combines them with a response body.
+    ///
+    /// Server-side errors become tool errors carrying the server's code
+    /// and message, so Claude can tell a refused action from a success.
+    async fn respond(&self, body: ResponseBody) -> ToolOutput {
+        match self.session() {
+            Ok(session) => self.respond_in(&session, body).await,
+            Err(_) => respond_with(body, Vec::new(), None),
         }
+    }
 
-        // Fall back to legacy single-file path for migration
-        let legacy = base.join("token");
-        if let Ok(contents) = std::fs::read_to_string(&legacy) {
-            let token = contents.trim().to_owned();
-            if !token.is_empty() {
-                tracing::info!(username, "token.migrating_legacy_file");
-                // Migrate: write to per-username, delete legacy
-                self.write_token_for(username, &token);
-                let _ = std::fs::remove_file(&legacy);
+    /// Like [`Self::respond`], draining the events of a given session.
+    async fn respond_in(&self, session: &Session, body: ResponseBody) -> ToolOutput {
+        self.respond_with_events(session, body, Vec::new()).await
+    }
+
+    /// Like [`Self::respond_in`], for tools that drained some events already.
+    ///
+    /// `earlier` events are reported ahead of the ones still buffered.
+    async fn respond_with_events(
+        &self,
+        session: &Session,
+        body: ResponseBody,
+        mut earlier: Vec<PushEvent>,
+    ) -> ToolOutput {
+        earlier.extend(session.events.lock().await.drain());
+        respond_with(body, earlier, Some(session.filter()))
+    }
+
+    /// Returns where per-character data of one `kind` (maps, triggers, ...)
+    /// is stored for `username`: `<token_path>/<kind>/<username>.json`.
+    ///
+    /// Usernames that could escape that directory are refused.
+    fn character_file(
+        &self,
+        kind: &str,
+        username: &str,
+    ) -> Result<std::path::PathBuf, rmcp::ErrorData> {
+        validate_username(username)
+            .map_err(|err| rmcp::ErrorData::invalid_params(err.to_string(), None))?;
+        let base = expand_tilde(&self.token_path);
+        Ok(std::path::Path::new(&base)
+            .join(kind)
+            .join(format!("{username}.json")))
+    }
+
+    /// Saves a token for `username`, logging rather than failing if it
+    /// cannot be stored. Returns whether it was saved.
+    fn write_token_for(&self, username: &str, token: &str) -> bool {
+        match self.tokens.write(username, token) {
+            Ok(()) => true,
+            Err(err) => {
+                tracing::warn!(error = %err, username, "token.file.write.failed");
+                false
             }
-            return token;
         }
+    }
+}
+
+/// Combines a response body with the events that came with it, to be
+/// reported through `filter` if given.
+fn respond_with(
+    body: ResponseBody,
+    events: Vec<PushEvent>,
+    filter: Option<EventFilter>,
+) -> ToolOutput {
+    ToolOutput::Game {
+        body,
+        events,
+        filter,
+    }
+}
+
+/// What a tool produced, before it is rendered for the MCP client.
+///
+/// `batch` takes each step's body and events from here rather than from
+/// the rendered text.
+#[derive(Debug)]
+enum ToolOutput {
+    /// A game server response and the events that came with it.
+    Game {
+        body: ResponseBody,
+        events: Vec<PushEvent>,
+        filter: Option<EventFilter>,
+    },
+    /// A result the client worked out itself.
+    Local(Value),
+}
 
-        String::new()
+impl ToolOutput {
+    /// Returns the response body and every event, unfiltered.
+    fn into_parts(self) -> (ResponseBody, Vec<PushEvent>) {
+        match self {
+            Self::Game { body, events, .. } => (body, events),
+            Self::Local(Value::Object(result)) => (ResponseBody::Success(result), Vec::new()),
+            Self::Local(other) => (
+                ResponseBody::Success(serde_json::Map::from_iter([("result".to_owned(), other)])),
+                Vec::new(),
+            ),
+        }
     }
 
-    /// Writes a token for a specific username, creating directories as needed.
+    /// Renders the output as the tool result the MCP client sees.
     ///
-    /// Tokens are stored at `<token_path>/tokens/<username>`.
-    fn write_token_for(&self, username: &str, token: &str) {
-        let base = expand_tilde(&self.token_path);
-        let base = std::path::Path::new(&base);
-        let tokens_dir = base.join("tokens");
-        if let Err(err) = std::fs::create_dir_all(&tokens_dir) {
-            tracing::warn!(error = %err, "token.dir.create.failed");
-            return;
+    /// Server-side errors become tool errors carrying the server's code
+    /// and message.
+    fn into_result(self) -> CallToolResult {
+        let (body, events, filter) = match self {
+            Self::Game {
+                body,
+                events,
+                filter,
+            } => (body, events, filter),
+            Self::Local(result) => {
+                return CallToolResult::success(vec![Content::text(result.to_string())]);
+            }
+        };
+        let Filtered { shown, hidden } = match &filter {
+            Some(filter) => filter.apply(&events),
+            None => Filtered {
+                shown: events.iter().collect(),
+                hidden: BTreeMap::new(),
+            },
+        };
+        let events: Vec<Value> = shown.into_iter().map(PushEvent::to_message).collect();
+        let (mut combined, failed) = match body {
+            ResponseBody::Success(result) => (
+                serde_json::json!({
+                    "result": result,
+                    "events": events,
+                }),
+                false,
+            ),
+            ResponseBody::Error { error } => (
+                serde_json::json!({
+                    "error": error,
+                    "events": events,
+                }),
+                true,
+            ),
+        };
+        if !hidden.is_empty() {
+            combined["events_hidden"] = serde_json::json!(hidden);
         }
-        let path = tokens_dir.join(username);
-        if let Err(err) = std::fs::write(&path, token) {
-            tracing::warn!(error = %err, username, "token.file.write.failed");
+
+        let content = vec![Content::text(combined.to_string())];
+        if failed {
+            CallToolResult::error(content)
+        } else {
+            CallToolResult::success(content)
         }
     }
 }
 
+impl IntoCallToolResult for ToolOutput {
+    fn into_call_tool_result(self) -> Result<CallToolResult, rmcp::ErrorData> {
+        Ok(self.into_result())
+    }
+}
+
+/// The error for tools used before `connect`.
+fn not_connected() -> rmcp::ErrorData {
+    rmcp::ErrorData::invalid_request("Not connected to game server — call `connect` first", None)
+}
+
+/// Advertises the optional `session` argument on `tool`.
+fn add_session_param(tool: &mut Tool) {
+    let schema = Arc::make_mut(&mut tool.input_schema);
+    let properties = schema
+        .entry("properties")
+        .or_insert_with(|| Value::Object(serde_json::Map::new()));
+    if let Value::Object(properties) = properties {
+        properties.insert(
+            SESSION_ARG.to_owned(),
+            serde_json::json!({
+                "type": "string",
+                "description": "Character session to act in (see session_list). Defaults to the active session.",
+            }),
+        );
+    }
+}
+
+/// Returns the result of a tool answered by the client itself.
+fn local_result(result: Value) -> ToolOutput {
+    ToolOutput::Local(result)
+}
+
+/// Parses a tool's arguments as its parameter type.
+fn params<T: DeserializeOwned>(arguments: JsonObject) -> Result<Parameters<T>, rmcp::ErrorData> {
+    parse_json_object(arguments).map(Parameters)
+}
+
+/// Reports one step of a batch or alias: its `result` or `error`, and
+/// whether it failed.
+fn body_outcome(body: ResponseBody) -> (Value, bool) {
+    match body {
+        ResponseBody::Success(result) => (serde_json::json!({ "result": result }), false),
+        ResponseBody::Error { error } => (serde_json::json!({ "error": error }), true),
+    }
+}
+
+/// Resolves a `wyvern://` URI to the game resource it names.
+fn resource_for_uri(uri: &str) -> Result<GameResource, rmcp::ErrorData> {
+    GameResource::from_uri(uri).ok_or_else(|| {
+        rmcp::ErrorData::resource_not_found(format!("unknown resource: {uri}"), None)
+    })
+}
+
 /// Expands a leading `~` in a path to the user's home directory.
-fn expand_tilde(path: &str) -> String {
+pub fn expand_tilde(path: &str) -> String {
     if let Some(rest) = path.strip_prefix("~/") {
         if let Some(home) = std::env::var_os("HOME") {
             return format!("{}/{rest}", home.to_string_lossy());
@@ -992,3 +2355,769 @@ fn expand_tilde(path: &str) -> String {
     }
     path.to_owned()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex as StdMutex;
+
+    use rmcp::model::ResourceUpdatedNotificationParam;
+    use rmcp::service::RunningService;
+    use rmcp::{ClientHandler, RoleClient, ServiceExt};
+    use serde_json::json;
+
+    use super::*;
+    use crate::mock::{MockServer, Reply, Script};
+
+    #[test]
+    fn raw_command_is_hidden_unless_allowed() {
+        let handler = GameHandler::new(
+            "ws://localhost:8080/ws".into(),
+            "/tmp".into(),
+            HandlerOptions::default(),
+        );
+        assert!(!handler.tool_router.has_route("raw_command"));
+        assert!(handler.tool_router.has_route("look"));
+
+        let options = HandlerOptions {
+            allow_raw: true,
+            ..HandlerOptions::default()
+        };
+        let handler = GameHandler::new("ws://localhost:8080/ws".into(), "/tmp".into(), options);
+        assert!(handler.tool_router.has_route("raw_command"));
+    }
+
+    #[tokio::test]
+    async fn usernames_that_escape_the_token_path_are_refused() {
+        let handler = GameHandler::new(
+            "ws://localhost:1/ws".into(),
+            "/tmp".into(),
+            HandlerOptions::default(),
+        );
+        for username in ["../../x", "a/b", ".hidden", ""] {
+            let params = ConnectParams {
+                username: Some(username.to_owned()),
+                token: Some("secret".to_owned()),
+            };
+            let err = handler
+                .connect(Parameters(params))
+                .await
+                .expect_err("invalid username");
+            assert!(err.message.contains("invalid username"), "{}", err.message);
+        }
+        assert!(handler.sessions.all().is_empty());
+    }
+
+    #[tokio::test]
+    async fn every_listed_tool_is_dispatched() {
+        let token_path =
+            std::env::temp_dir().join(format!("wyvern-dispatch-{}", std::process::id()));
+        let options = HandlerOptions {
+            allow_raw: true,
+            ..HandlerOptions::default()
+        };
+        let handler = GameHandler::new(
+            "ws://localhost:1/ws".into(),
+            token_path.display().to_string(),
+            options,
+        );
+        // Empty arguments fail or act on no session, but never go unrouted.
+        for tool in handler.tool_router.list_all() {
+            if let Err(err) = handler.dispatch(&tool.name, JsonObject::new()).await {
+                assert_ne!(
+                    err.message, "tool not found",
+                    "{} is not dispatched",
+                    tool.name
+                );
+            }
+        }
+        let _ = std::fs::remove_dir_all(token_path);
+    }
+
+    /// An MCP client talking to a fresh handler backed by a fresh mock
+    /// world, with its own token path.
+    struct Harness {
+        mock: MockServer,
+        handler: GameHandler,
+        client: RunningService<RoleClient, ()>,
+        token_path: std::path::PathBuf,
+    }
+
+    impl Harness {
+        async fn start(name: &str) -> Self {
+            Self::with_script(name, Script::world()).await
+        }
+
+        async fn with_script(name: &str, script: Script) -> Self {
+            let mock = MockServer::start(([127, 0, 0, 1], 0).into(), script)
+                .await
+                .expect("mock server");
+            let token_path =
+                std::env::temp_dir().join(format!("wyvern-tools-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&token_path);
+            let options = HandlerOptions {
+                allow_raw: true,
+                ..HandlerOptions::default()
+            };
+            let handler = GameHandler::new(mock.url(), token_path.display().to_string(), options);
+            let client = attach(&handler, ()).await;
+            Self {
+                mock,
+                handler,
+                client,
+                token_path,
+            }
+        }
+
+        /// Calls `tool` and returns its result and the actions it sent.
+        async fn try_call(
+            &self,
+            tool: &str,
+            arguments: Value,
+        ) -> Result<(CallToolResult, Vec<String>), rmcp::ServiceError> {
+            let sent_before = self.mock.requests().len();
+            let Value::Object(arguments) = arguments else {
+                panic!("arguments must be an object");
+            };
+            let result = self
+                .client
+                .call_tool(CallToolRequestParams {
+                    meta: None,
+                    name: tool.to_owned().into(),
+                    arguments: Some(arguments),
+                    task: None,
+                })
+                .await?;
+            let sent = self.mock.requests()[sent_before..]
+                .iter()
+                .map(|(action, _)| action.clone())
+                .collect();
+            Ok((result, sent))
+        }
+
+        /// Calls `tool`, which must succeed, and returns its JSON output.
+        async fn call(&self, tool: &str, arguments: Value) -> Value {
+            let (result, _) = self
+                .try_call(tool, arguments)
+                .await
+                .unwrap_or_else(|err| panic!("`{tool}` failed: {err}"));
+            let text = text_of(&result);
+            assert_ne!(result.is_error, Some(true), "`{tool}` failed: {text}");
+            serde_json::from_str(&text).expect("json output")
+        }
+    }
+
+    impl Drop for Harness {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.token_path);
+        }
+    }
+
+    /// Serves `handler` to another MCP client, as the HTTP transport does.
+    async fn attach<C: ClientHandler>(
+        handler: &GameHandler,
+        client: C,
+    ) -> RunningService<RoleClient, C> {
+        let handler = handler.for_client();
+        let (server_io, client_io) = tokio::io::duplex(1 << 16);
+        tokio::spawn(async move {
+            if let Ok(service) = handler.serve(server_io).await {
+                let _ = service.waiting().await;
+            }
+        });
+        client.serve(client_io).await.expect("mcp client")
+    }
+
+    /// A client that records which resources it is told changed.
+    #[derive(Debug, Clone, Default)]
+    struct UpdateLog(Arc<StdMutex<Vec<String>>>);
+
+    impl UpdateLog {
+        fn uris(&self) -> Vec<String> {
+            self.0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()
+        }
+
+        /// Waits up to five seconds for the first update.
+        async fn wait(&self) -> Vec<String> {
+            let _ = tokio::time::timeout(Duration::from_secs(5), async {
+                while self.uris().is_empty() {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await;
+            self.uris()
+        }
+    }
+
+    fn room_uri() -> String {
+        GameResource::Room.uri().to_owned()
+    }
+
+    impl ClientHandler for UpdateLog {
+        async fn on_resource_updated(
+            &self,
+            params: ResourceUpdatedNotificationParam,
+            _context: NotificationContext<RoleClient>,
+        ) {
+            self.0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(params.uri);
+        }
+    }
+
+    fn text_of(result: &CallToolResult) -> String {
+        result
+            .content
+            .first()
+            .and_then(|content| content.as_text())
+            .map(|content| content.text.clone())
+            .unwrap_or_default()
+    }
+
+    /// Game tools that pass straight through to one server action: the
+    /// tool, its JSON arguments, and the action it should send.
+    const GAME_TOOLS: &[(&str, &str, &str)] = &[
+        ("look", "{}", "look"),
+        ("move_direction", r#"{"direction":"north"}"#, "move"),
+        ("map", "{}", "map"),
+        ("attack", r#"{"target":"goblin"}"#, "attack"),
+        (
+            "use_ability",
+            r#"{"ability":"fireball","target":"goblin"}"#,
+            "use_ability",
+        ),
+        ("flee", "{}", "flee"),
+        ("status", "{}", "status"),
+        ("inventory", "{}", "inventory"),
+        ("get_item", r#"{"item":"sword"}"#, "get"),
+        ("drop_item", r#"{"item":"sword"}"#, "drop"),
+        ("equip", r#"{"item":"sword"}"#, "equip"),
+        ("use_item", r#"{"item":"potion"}"#, "use_item"),
+        ("say", r#"{"message":"hello"}"#, "say"),
+        ("tell", r#"{"player":"bob","message":"hi"}"#, "tell"),
+        ("shout", r#"{"message":"hear ye"}"#, "shout"),
+        ("emote", r#"{"action":"waves"}"#, "emote"),
+        ("who", "{}", "who"),
+        ("channel", r#"{"name":"party","message":"hi"}"#, "channel"),
+        ("talk", r#"{"target":"smith"}"#, "talk"),
+        (
+            "dialogue_select",
+            r#"{"npc":"smith","option":1}"#,
+            "dialogue_select",
+        ),
+        ("party_invite", r#"{"player":"bob"}"#, "party_invite"),
+        ("party_accept", "{}", "party_accept"),
+        ("party_leave", "{}", "party_leave"),
+        ("party_kick", r#"{"player":"bob"}"#, "party_kick"),
+        ("party_list", "{}", "party_list"),
+        ("matchmake", r#"{"role":"tank"}"#, "matchmake"),
+        ("companion", r#"{"command":"follow"}"#, "companion"),
+        ("companion_status", "{}", "companion_status"),
+        ("companion_memory", "{}", "companion_memory"),
+        (
+            "companion_memory_write",
+            r#"{"text":"Met Bob."}"#,
+            "companion_memory_write",
+        ),
+        ("character_info", "{}", "character_info"),
+        ("abilities", "{}", "abilities"),
+        ("quests", "{}", "quests"),
+        ("leaderboard", "{}", "leaderboard"),
+        (
+            "suggest_description",
+            r#"{"context":"a tavern"}"#,
+            "suggest_description",
+        ),
+        ("buy", r#"{"shop_id":"smith","item":"sword"}"#, "buy"),
+        ("sell", r#"{"shop_id":"smith","item":"sword"}"#, "sell"),
+        ("accept_quest", r#"{"quest_id":"q1"}"#, "accept_quest"),
+        ("complete_quest", r#"{"quest_id":"q1"}"#, "complete_quest"),
+        ("guild_create", r#"{"name":"Wyrms"}"#, "guild_create"),
+        ("guild_invite", r#"{"player":"bob"}"#, "guild_invite"),
+        ("guild_leave", "{}", "guild_leave"),
+        ("guild_info", "{}", "guild_info"),
+        ("guild_deposit", r#"{"amount":5}"#, "guild_deposit"),
+        ("raw_command", r#"{"action":"who"}"#, "who"),
+    ];
+
+    /// Tools covered by the focused tests below rather than `GAME_TOOLS`.
+    const FEATURE_TOOLS: &[&str] = &[
+        "connect",
+        "disconnect",
+        "connection_status",
+        "session_list",
+        "session_switch",
+        "set_event_filter",
+        "accounts_list",
+        "account_forget",
+        "account_rotate_token",
+        "travel_to",
+        "batch",
+        "trigger_add",
+        "trigger_list",
+        "trigger_remove",
+        "alias_define",
+        "alias_list",
+        "alias_run",
+    ];
+
+    #[tokio::test]
+    async fn game_tools_send_their_actions() {
+        let harness = Harness::start("game").await;
+        harness.call("connect", json!({ "username": "aria" })).await;
+
+        let mut failures = Vec::new();
+        for &(tool, arguments, action) in GAME_TOOLS {
+            let arguments = serde_json::from_str(arguments).expect("valid arguments");
+            match harness.try_call(tool, arguments).await {
+                Ok((result, sent)) => {
+                    let text = text_of(&result);
+                    if result.is_error == Some(true) || text.contains(r#""error""#) {
+                        failures.push(format!("`{tool}` was refused: {text}"));
+                    } else if sent != [action] {
+                        failures.push(format!("`{tool}` sent {sent:?}, not {action:?}"));
+                    }
+                }
+                Err(err) => failures.push(format!("`{tool}` failed: {err}")),
+            }
+        }
+        assert!(failures.is_empty(), "{failures:#?}");
+
+        let tools = harness.client.list_all_tools().await.expect("tool list");
+        let uncovered: Vec<_> = tools
+            .iter()
+            .map(|tool| tool.name.as_ref())
+            .filter(|name| {
+                !GAME_TOOLS.iter().any(|(tool, ..)| tool == name) && !FEATURE_TOOLS.contains(name)
+            })
+            .collect();
+        assert!(uncovered.is_empty(), "no test covers {uncovered:?}");
+    }
+
+    #[tokio::test]
+    async fn pushed_events_reach_the_next_response() {
+        let harness = Harness::start("events").await;
+        harness.call("connect", json!({ "username": "aria" })).await;
+
+        // The mock pushes a combat update right after answering an attack,
+        // so it is reported with the attack or, at the latest, the next call.
+        let attack = harness.call("attack", json!({ "target": "goblin" })).await;
+        let look = harness.call("look", json!({})).await;
+        let events: Vec<_> = [&attack, &look]
+            .into_iter()
+            .flat_map(|output| output["events"].as_array().cloned().unwrap_or_default())
+            .collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["data"]["event"], "combat_update");
+        let output = harness.call("look", json!({})).await;
+        assert_eq!(output["events"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn accounts_save_rotate_and_forget_tokens() {
+        let harness = Harness::start("accounts").await;
+        let token_file = harness.token_path.join("tokens").join("aria");
+
+        // The mock creates a new account for a login without a token.
+        harness.call("connect", json!({ "username": "aria" })).await;
+        assert_eq!(
+            std::fs::read_to_string(&token_file).expect("saved token"),
+            "mock-token"
+        );
+        let status = harness.call("connection_status", json!({})).await;
+        assert_eq!(status["session"], "aria");
+        assert_eq!(status["state"], "connected");
+        let accounts = harness.call("accounts_list", json!({})).await;
+        assert_eq!(
+            accounts["accounts"],
+            json!([{ "username": "aria", "active": true, "open": true }])
+        );
+
+        let rotated = harness.call("account_rotate_token", json!({})).await;
+        assert_eq!(rotated["result"]["saved"], true);
+        assert!(rotated["result"].get("token").is_none());
+        assert_eq!(
+            std::fs::read_to_string(&token_file).expect("saved token"),
+            "mock-token-2"
+        );
+
+        harness
+            .call("account_forget", json!({ "username": "aria" }))
+            .await;
+        assert!(!token_file.exists());
+    }
+
+    #[tokio::test]
+    async fn each_character_gets_its_own_session() {
+        let harness = Harness::start("sessions").await;
+        harness.call("connect", json!({ "username": "aria" })).await;
+        harness.call("connect", json!({ "username": "brom" })).await;
+
+        let sessions = harness.call("session_list", json!({})).await;
+        assert_eq!(sessions["active"], "brom");
+        assert_eq!(sessions["sessions"].as_array().map(Vec::len), Some(2));
+        let switched = harness
+            .call("session_switch", json!({ "name": "aria" }))
+            .await;
+        assert_eq!(switched["result"]["active"], "aria");
+
+        // `session` picks one per call without changing the active one.
+        let (_, sent) = harness
+            .try_call("status", json!({ "session": "brom" }))
+            .await
+            .expect("status");
+        assert_eq!(sent, ["status"]);
+        assert_eq!(
+            harness.handler.sessions.active_name().as_deref(),
+            Some("aria")
+        );
+
+        // Each disconnect closes the active session.
+        let output = harness.call("disconnect", json!({})).await;
+        assert_eq!(output["active_session"], "brom");
+        harness.call("disconnect", json!({})).await;
+        assert!(harness.handler.sessions.all().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_refused_login_closes_the_new_session() {
+        let script = Script::world().on("connect", |params| match params["username"].as_str() {
+            Some("aria") => Reply::ok(json!({ "room": { "id": "square" } })),
+            _ => Reply::error("auth_failed", "Unknown character."),
+        });
+        let harness = Harness::with_script("refused", script).await;
+        harness.call("connect", json!({ "username": "aria" })).await;
+
+        let (refused, _) = harness
+            .try_call("connect", json!({ "username": "brom" }))
+            .await
+            .expect("tool call");
+        assert_eq!(refused.is_error, Some(true));
+        assert!(text_of(&refused).contains("auth_failed"));
+        assert!(harness.handler.sessions.get("brom").is_none());
+        assert_eq!(
+            harness.handler.sessions.active_name().as_deref(),
+            Some("aria")
+        );
+    }
+
+    #[tokio::test]
+    async fn a_refused_relogin_keeps_the_previous_login() {
+        let script = Script::world().on("connect", |params| match params["token"].as_str() {
+            Some("bad") => Reply::error("auth_failed", "Wrong token."),
+            _ => Reply::ok(json!({ "room": { "id": "square" } })),
+        });
+        let harness = Harness::with_script("relogin", script).await;
+        harness
+            .call("connect", json!({ "username": "aria", "token": "first" }))
+            .await;
+        harness
+            .call("connect", json!({ "username": "brom", "token": "second" }))
+            .await;
+
+        let (refused, _) = harness
+            .try_call("connect", json!({ "username": "aria", "token": "bad" }))
+            .await
+            .expect("tool call");
+        assert_eq!(refused.is_error, Some(true));
+        assert_eq!(
+            harness.handler.sessions.active_name().as_deref(),
+            Some("brom")
+        );
+
+        let aria = harness.handler.sessions.get("aria").expect("still open");
+        assert_eq!(
+            aria.connection.lock().await.token().await.as_deref(),
+            Some("first")
+        );
+        let (action, params) = harness.mock.requests().pop().expect("requests");
+        assert_eq!(action, "connect");
+        assert_eq!(params["token"], "first");
+    }
+
+    #[tokio::test]
+    async fn event_filters_are_per_session() {
+        let script = Script::world().on("look", |_| {
+            Reply::ok(json!({ "room": { "id": "square", "name": "Town Square" } }))
+                .with_event(json!({ "event": "shout", "from": "Cato", "message": "hello" }))
+                .with_event(json!({ "event": "say", "from": "Spammer", "message": "buy gold" }))
+                .with_event(json!({ "event": "combat_update", "attacker": "Spammer", "hp": 7 }))
+        });
+        let harness = Harness::with_script("filter", script).await;
+        harness.call("connect", json!({ "username": "aria" })).await;
+        harness.call("connect", json!({ "username": "brom" })).await;
+
+        let filter = harness
+            .call(
+                "set_event_filter",
+                json!({ "session": "aria", "verbosity": "minimal", "hide_players": ["Spammer"] }),
+            )
+            .await;
+        assert_eq!(filter["session"], "aria");
+        assert_eq!(filter["event_filter"]["verbosity"], "minimal");
+        let filter = harness
+            .call(
+                "set_event_filter",
+                json!({ "session": "brom", "hide_events": ["say"] }),
+            )
+            .await;
+        assert_eq!(filter["event_filter"]["verbosity"], "everything");
+
+        // Events trail the look response, so the status call after it
+        // picks up whichever of them the look missed.
+        let mut seen = BTreeMap::new();
+        for session in ["aria", "brom"] {
+            let mut shown = Vec::new();
+            let mut hidden = BTreeMap::new();
+            for tool in ["look", "status"] {
+                let output = harness.call(tool, json!({ "session": session })).await;
+                for event in output["events"].as_array().expect("events") {
+                    shown.push(event["data"]["event"].as_str().expect("kind").to_owned());
+                }
+                if let Some(counts) = output["events_hidden"].as_object() {
+                    for (kind, count) in counts {
+                        *hidden.entry(kind.clone()).or_insert(0) += count.as_u64().expect("count");
+                    }
+                }
+            }
+            seen.insert(session, (shown, hidden));
+        }
+
+        assert_eq!(
+            seen["aria"],
+            (
+                vec!["combat_update".to_owned()],
+                BTreeMap::from([("say".to_owned(), 1), ("shout".to_owned(), 1)])
+            )
+        );
+        assert_eq!(
+            seen["brom"],
+            (
+                vec!["shout".to_owned(), "combat_update".to_owned()],
+                BTreeMap::from([("say".to_owned(), 1)])
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn every_attached_client_is_notified() {
+        let harness = Harness::start("peers").await;
+        let logs = [UpdateLog::default(), UpdateLog::default()];
+        let mut clients = Vec::new();
+        for log in &logs {
+            let client = attach(&harness.handler, log.clone()).await;
+            client
+                .subscribe(SubscribeRequestParams {
+                    meta: None,
+                    uri: room_uri(),
+                })
+                .await
+                .expect("subscribed");
+            clients.push(client);
+        }
+
+        harness.call("connect", json!({ "username": "aria" })).await;
+        harness.call("look", json!({})).await;
+        harness
+            .call("move_direction", json!({ "direction": "north" }))
+            .await;
+
+        for log in &logs {
+            assert_eq!(log.wait().await, [room_uri()], "a client missed the update");
+        }
+    }
+
+    #[tokio::test]
+    async fn only_subscribed_clients_are_notified() {
+        let harness = Harness::start("subscriptions").await;
+        let (subscriber, bystander, quitter) = (
+            UpdateLog::default(),
+            UpdateLog::default(),
+            UpdateLog::default(),
+        );
+        let _bystander = attach(&harness.handler, bystander.clone()).await;
+        let mut clients = Vec::new();
+        for log in [&subscriber, &quitter] {
+            let client = attach(&harness.handler, log.clone()).await;
+            client
+                .subscribe(SubscribeRequestParams {
+                    meta: None,
+                    uri: room_uri(),
+                })
+                .await
+                .expect("subscribed");
+            clients.push(client);
+        }
+        clients[1]
+            .unsubscribe(UnsubscribeRequestParams {
+                meta: None,
+                uri: room_uri(),
+            })
+            .await
+            .expect("unsubscribed");
+
+        harness.call("connect", json!({ "username": "aria" })).await;
+        harness.call("look", json!({})).await;
+        harness
+            .call("move_direction", json!({ "direction": "north" }))
+            .await;
+
+        // The others would have been told in the same pass.
+        assert_eq!(subscriber.wait().await, [room_uri()]);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(bystander.uris().is_empty(), "never subscribed");
+        assert!(quitter.uris().is_empty(), "unsubscribed");
+    }
+
+    #[tokio::test]
+    async fn travel_to_walks_the_explored_map() {
+        let harness = Harness::start("travel").await;
+        harness.call("connect", json!({ "username": "aria" })).await;
+        harness
+            .call("move_direction", json!({ "direction": "north" }))
+            .await;
+
+        let (result, sent) = harness
+            .try_call("travel_to", json!({ "destination": "Town Square" }))
+            .await
+            .expect("travel_to");
+        let output: Value = serde_json::from_str(&text_of(&result)).expect("json output");
+        assert_eq!(output["result"]["arrived"], true);
+        assert_eq!(output["result"]["moves"], json!(["south"]));
+        assert_eq!(sent, ["move"]);
+    }
+
+    #[tokio::test]
+    async fn trigger_actions_update_the_map() {
+        let harness = Harness::start("trigger-map").await;
+        harness.call("connect", json!({ "username": "aria" })).await;
+        harness
+            .call(
+                "trigger_add",
+                json!({
+                    "event": "combat_update",
+                    "action": "move",
+                    "params": { "direction": "north" }
+                }),
+            )
+            .await;
+        harness.call("attack", json!({ "target": "goblin" })).await;
+
+        let session = harness.handler.sessions.get("aria").expect("session");
+        let moved = tokio::time::timeout(Duration::from_secs(5), async {
+            while session.world.current().as_deref() != Some("gate") {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(moved.is_ok(), "the map missed the trigger's move");
+
+        let (result, sent) = harness
+            .try_call("travel_to", json!({ "destination": "Town Square" }))
+            .await
+            .expect("travel_to");
+        let output: Value = serde_json::from_str(&text_of(&result)).expect("json output");
+        assert_eq!(output["result"]["moves"], json!(["south"]));
+        assert_eq!(sent, ["move"]);
+    }
+
+    #[tokio::test]
+    async fn batch_reports_each_step_and_stops_on_interrupts() {
+        let harness = Harness::start("batch").await;
+        harness.call("connect", json!({ "username": "aria" })).await;
+
+        let output = harness
+            .call(
+                "batch",
+                json!({ "steps": [{ "tool": "status" }, { "tool": "look" }] }),
+            )
+            .await;
+        assert_eq!(output["result"]["completed"], true);
+        assert_eq!(output["result"]["steps"][0]["tool"], "status");
+        assert_eq!(output["result"]["steps"][0]["result"]["hp"], 20);
+        assert_eq!(output["result"]["steps"][1]["tool"], "look");
+
+        // The attack's combat update arrives by the next step at the latest,
+        // stops the batch there and is still reported.
+        let output = harness
+            .call(
+                "batch",
+                json!({ "steps": [
+                    { "tool": "attack", "arguments": { "target": "goblin" } },
+                    { "tool": "look" },
+                    { "tool": "status" },
+                ] }),
+            )
+            .await;
+        assert_eq!(output["result"]["completed"], false);
+        assert_eq!(
+            output["result"]["stopped"],
+            "interrupted by combat_update event"
+        );
+        let steps = output["result"]["steps"].as_array().map_or(0, Vec::len);
+        assert!((1..=2).contains(&steps), "ran {steps} steps");
+        assert_eq!(output["events"][0]["data"]["event"], "combat_update");
+    }
+
+    #[tokio::test]
+    async fn triggers_are_added_listed_and_removed() {
+        let harness = Harness::start("triggers").await;
+        harness.call("connect", json!({ "username": "aria" })).await;
+
+        let added = harness
+            .call(
+                "trigger_add",
+                json!({ "event": "tell", "action": "status" }),
+            )
+            .await;
+        let id = added["added"]["id"].clone();
+        let listed = harness.call("trigger_list", json!({})).await;
+        assert_eq!(listed["triggers"][0]["id"], id);
+        harness.call("trigger_remove", json!({ "id": id })).await;
+        let listed = harness.call("trigger_list", json!({})).await;
+        assert_eq!(listed["triggers"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn aliases_expand_their_arguments() {
+        let harness = Harness::start("aliases").await;
+        harness.call("connect", json!({ "username": "aria" })).await;
+
+        harness
+            .call(
+                "alias_define",
+                json!({
+                    "name": "greet",
+                    "steps": [{ "action": "say", "params": { "message": "Hail, $1!" } }],
+                }),
+            )
+            .await;
+        let listed = harness.call("alias_list", json!({})).await;
+        assert_eq!(listed["aliases"][0]["name"], "greet");
+
+        let output = harness
+            .call("alias_run", json!({ "name": "greet", "args": ["Bob"] }))
+            .await;
+        assert_eq!(output["result"]["completed"], true);
+        let (action, params) = harness.mock.requests().pop().expect("request");
+        assert_eq!(action, "say");
+        assert_eq!(params["message"], "Hail, Bob!");
+
+        let look = json!({ "action": "look" });
+        harness
+            .call(
+                "alias_define",
+                json!({ "name": "stare", "steps": vec![look; MAX_BATCH_STEPS + 1] }),
+            )
+            .await;
+        let sent_before = harness.mock.requests().len();
+        assert!(harness
+            .try_call("alias_run", json!({ "name": "stare" }))
+            .await
+            .is_err());
+        assert_eq!(harness.mock.requests().len(), sent_before);
+    }
+}
@@ -0,0 +1,454 @@
+//! MUD-style triggers that react to push events automatically.
+//!
+//! A trigger pairs a match on incoming events with a server action, e.g.
+//! "when a `combat_update` leaves me under 30% HP, use a healing potion".
+//! Rules are saved per character at `<token_path>/triggers/<username>.json`.
+//!
+//! A background task watches the connection's live event feed and sends
+//! the action for every matching rule, at most once per cooldown. Actions
+//! take the same path as tool calls, so the map, the published resources
+//! and observers follow what they did. The outcome is reported as a
+//! `trigger_fired` event, so Claude learns what happened with the next
+//! tool response.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::sync::broadcast;
+
+use crate::connection::ConnectionHandle;
+use crate::protocol::{PushEvent, ResponseBody};
+use crate::store;
+
+/// Event kind used to report trigger outcomes.
+pub const TRIGGER_FIRED_EVENT: &str = "trigger_fired";
+
+/// Shortest cooldown a trigger may have.
+///
+/// A rule whose action causes the event that fires it would otherwise
+/// loop as fast as the server answers.
+const MIN_COOLDOWN_SECS: u64 = 1;
+
+/// Errors from managing triggers.
+#[derive(Debug, thiserror::Error)]
+pub enum TriggerError {
+    /// Triggers belong to a character, so one must be logged in.
+    #[error("not logged in; call `connect` first")]
+    NoCharacter,
+    /// The rule would fire on every single event.
+    #[error("a trigger needs an `event` type, a `path` to match, or both")]
+    Unconstrained,
+    /// The rule could fire itself in a tight loop.
+    #[error("`cooldown_secs` must be at least {MIN_COOLDOWN_SECS}")]
+    CooldownTooShort,
+}
+
+/// How the value found at a trigger's path is compared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchOp {
+    /// The path is present in the event.
+    #[default]
+    Exists,
+    /// Equal to `value`.
+    Eq,
+    /// Not equal to `value`.
+    Ne,
+    /// Numerically less than `value`.
+    Lt,
+    /// Numerically at most `value`.
+    Le,
+    /// Numerically greater than `value`.
+    Gt,
+    /// Numerically at least `value`.
+    Ge,
+    /// Text containing `value` (case-insensitive), or a list containing it.
+    Contains,
+}
+
+/// What a trigger reacts to and what it does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TriggerRule {
+    /// Event type to react to (e.g. "tell", "death"). Any type if omitted.
+    pub event: Option<String>,
+    /// Dotted path into the event data (e.g. "hp" or "attacker.name").
+    /// Without one, every event of the given type matches.
+    pub path: Option<String>,
+    /// Comparison applied to the value at `path`. Defaults to `exists`.
+    #[serde(default)]
+    pub op: MatchOp,
+    /// Value to compare against.
+    pub value: Option<Value>,
+    /// Path of a value to divide by before comparing, for percentages.
+    /// For "below 30% HP": `path` = `hp`, `ratio_of` = `max_hp`,
+    /// `op` = `lt`, `value` = 0.3.
+    pub ratio_of: Option<String>,
+    /// Server action to send when the trigger fires.
+    pub action: String,
+    /// Parameters for the action.
+    #[serde(default)]
+    pub params: Map<String, Value>,
+    /// Minimum seconds between firings, at least 1. Defaults to 5.
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+fn default_cooldown_secs() -> u64 {
+    5
+}
+
+impl TriggerRule {
+    /// Returns true if `event` satisfies the rule's match.
+    fn matches(&self, event: &PushEvent) -> bool {
+        if self
+            .event
+            .as_deref()
+            .is_some_and(|kind| kind != event.kind())
+        {
+            return false;
+        }
+        let Some(path) = &self.path else {
+            return true;
+        };
+
+        let message = event.to_message();
+        let data = message.get("data").unwrap_or(&message);
+        let Some(found) = lookup(data, path) else {
+            return false;
+        };
+        let found = match &self.ratio_of {
+            Some(total) => match (found.as_f64(), lookup(data, total).and_then(Value::as_f64)) {
+                (Some(part), Some(total)) if total != 0.0 => Value::from(part / total),
+                _ => return false,
+            },
+            None => found.clone(),
+        };
+        compare(&found, self.op, self.value.as_ref())
+    }
+}
+
+/// A stored trigger.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trigger {
+    /// Identifier used to remove the trigger.
+    pub id: u32,
+    /// Match and action.
+    #[serde(flatten)]
+    pub rule: TriggerRule,
+}
+
+/// On-disk format of a character's triggers.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TriggerFile {
+    next_id: u32,
+    triggers: Vec<Trigger>,
+}
+
+#[derive(Debug, Default)]
+struct TriggersInner {
+    file: Option<PathBuf>,
+    stored: TriggerFile,
+    last_fired: HashMap<u32, Instant>,
+}
+
+/// The active character's triggers.
+#[derive(Debug, Clone, Default)]
+pub struct Triggers {
+    inner: Arc<Mutex<TriggersInner>>,
+}
+
+impl Triggers {
+    /// Switches to the triggers stored at `file`, loading them if it exists.
+    pub fn open(&self, file: PathBuf) {
+        let stored = store::load(&file);
+        *self.lock() = TriggersInner {
+            file: Some(file),
+            stored,
+            last_fired: HashMap::new(),
+        };
+    }
+
+    /// Disarms all triggers, e.g. on disconnect.
+    pub fn close(&self) {
+        *self.lock() = TriggersInner::default();
+    }
+
+    /// Adds and saves a trigger.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TriggerError::NoCharacter` if no character's triggers are
+    /// open, `TriggerError::Unconstrained` if the rule matches everything,
+    /// or `TriggerError::CooldownTooShort` if its cooldown is under a second.
+    pub fn add(&self, rule: TriggerRule) -> Result<Trigger, TriggerError> {
+        if rule.event.is_none() && rule.path.is_none() {
+            return Err(TriggerError::Unconstrained);
+        }
+        if rule.cooldown_secs < MIN_COOLDOWN_SECS {
+            return Err(TriggerError::CooldownTooShort);
+        }
+        let mut inner = self.lock();
+        if inner.file.is_none() {
+            return Err(TriggerError::NoCharacter);
+        }
+        inner.stored.next_id += 1;
+        let trigger = Trigger {
+            id: inner.stored.next_id,
+            rule,
+        };
+        inner.stored.triggers.push(trigger.clone());
+        inner.save();
+        Ok(trigger)
+    }
+
+    /// Lists the triggers in the order they were added.
+    pub fn list(&self) -> Vec<Trigger> {
+        self.lock().stored.triggers.clone()
+    }
+
+    /// Removes a trigger, returning true if it existed.
+    pub fn remove(&self, id: u32) -> bool {
+        let mut inner = self.lock();
+        let before = inner.stored.triggers.len();
+        inner.stored.triggers.retain(|trigger| trigger.id != id);
+        if inner.stored.triggers.len() == before {
+            return false;
+        }
+        inner.last_fired.remove(&id);
+        inner.save();
+        true
+    }
+
+    /// Returns the triggers that fire for `event` at `now`, starting
+    /// their cooldowns.
+    fn fire(&self, event: &PushEvent, now: Instant) -> Vec<Trigger> {
+        let mut inner = self.lock();
+        let TriggersInner {
+            stored, last_fired, ..
+        } = &mut *inner;
+        let mut fired = Vec::new();
+        for trigger in &stored.triggers {
+            // Rules saved before the minimum existed still get it.
+            let cooldown = Duration::from_secs(trigger.rule.cooldown_secs.max(MIN_COOLDOWN_SECS));
+            let cooling = last_fired
+                .get(&trigger.id)
+                .is_some_and(|at| now.saturating_duration_since(*at) < cooldown);
+            if !cooling && trigger.rule.matches(event) {
+                last_fired.insert(trigger.id, now);
+                fired.push(trigger.clone());
+            }
+        }
+        fired
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TriggersInner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl TriggersInner {
+    /// Writes the triggers to disk, if a character is selected.
+    fn save(&self) {
+        if let Some(file) = &self.file {
+            store::save(file, &self.stored);
+        }
+    }
+}
+
+/// Runs matching triggers as events arrive, until the feed closes.
+///
+/// Each action is sent with `send`, which returns the server's response
+/// body or why the command failed; outcomes are emitted on `connection`.
+pub async fn run_triggers<F>(
+    mut feed: broadcast::Receiver<PushEvent>,
+    triggers: Triggers,
+    connection: ConnectionHandle,
+    send: impl Fn(String, Value) -> F,
+) where
+    F: Future<Output = Result<ResponseBody, String>>,
+{
+    loop {
+        let event = match feed.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(skipped, "triggers.events.lagged");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        // Never react to our own reports.
+        if event.kind() == TRIGGER_FIRED_EVENT {
+            continue;
+        }
+
+        for trigger in triggers.fire(&event, Instant::now()) {
+            tracing::info!(
+                trigger.id = trigger.id,
+                trigger.action = %trigger.rule.action,
+                event.kind = event.kind(),
+                "triggers.fired"
+            );
+            let params = Value::Object(trigger.rule.params.clone());
+            let outcome = match send(trigger.rule.action.clone(), params).await {
+                Ok(ResponseBody::Success(result)) => serde_json::json!({ "result": result }),
+                Ok(ResponseBody::Error { error }) => serde_json::json!({ "error": error }),
+                Err(message) => serde_json::json!({ "error": { "message": message } }),
+            };
+            let mut data = serde_json::json!({
+                "event": TRIGGER_FIRED_EVENT,
+                "trigger": trigger.id,
+                "cause": event.kind(),
+                "action": trigger.rule.action,
+            });
+            if let (Some(data), Value::Object(outcome)) = (data.as_object_mut(), outcome) {
+                data.extend(outcome);
+            }
+            connection.emit(PushEvent::from_message(
+                serde_json::json!({ "type": "event", "data": data }),
+            ));
+        }
+    }
+}
+
+/// Follows a dotted path such as `attacker.name` or `items.0`.
+///
+/// A leading `$.` is accepted for familiarity with JSON path.
+fn lookup<'a>(data: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.strip_prefix("$.").unwrap_or(path);
+    path.split('.')
+        .try_fold(data, |value, segment| match value {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+/// Applies a trigger comparison.
+fn compare(found: &Value, op: MatchOp, expected: Option<&Value>) -> bool {
+    let Some(expected) = expected else {
+        return op == MatchOp::Exists;
+    };
+    let numbers = found.as_f64().zip(expected.as_f64());
+    match op {
+        MatchOp::Exists => true,
+        // Numbers compare by value, so 1 and 1.0 are equal.
+        MatchOp::Eq => numbers.map_or(found == expected, |(a, b)| (a - b).abs() < f64::EPSILON),
+        MatchOp::Ne => numbers.map_or(found != expected, |(a, b)| (a - b).abs() >= f64::EPSILON),
+        MatchOp::Lt => numbers.is_some_and(|(a, b)| a < b),
+        MatchOp::Le => numbers.is_some_and(|(a, b)| a <= b),
+        MatchOp::Gt => numbers.is_some_and(|(a, b)| a > b),
+        MatchOp::Ge => numbers.is_some_and(|(a, b)| a >= b),
+        MatchOp::Contains => match (found, expected) {
+            (Value::String(text), Value::String(needle)) => {
+                text.to_lowercase().contains(&needle.to_lowercase())
+            }
+            (Value::Array(items), needle) => items.contains(needle),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn rule(value: Value) -> TriggerRule {
+        serde_json::from_value(value).expect("valid rule")
+    }
+
+    fn hit(hp: u32) -> PushEvent {
+        PushEvent::from_message(json!({
+            "type": "event",
+            "data": {
+                "event": "combat_update",
+                "attacker": { "name": "Goblin" },
+                "target": "aria",
+                "damage": 3,
+                "hp": hp,
+                "max_hp": 100
+            }
+        }))
+    }
+
+    #[test]
+    fn ratio_rule_matches_low_hp() {
+        let heal = rule(json!({
+            "event": "combat_update",
+            "path": "hp",
+            "ratio_of": "max_hp",
+            "op": "lt",
+            "value": 0.3,
+            "action": "use_item",
+            "params": { "item": "healing potion" }
+        }));
+        assert!(heal.matches(&hit(25)));
+        assert!(!heal.matches(&hit(60)));
+
+        let goblin = rule(json!({
+            "path": "$.attacker.name",
+            "op": "contains",
+            "value": "gob",
+            "action": "flee"
+        }));
+        assert!(goblin.matches(&hit(60)));
+    }
+
+    #[test]
+    fn triggers_respect_cooldown_and_persist() {
+        let dir = std::env::temp_dir().join(format!("wyvern-triggers-{}", std::process::id()));
+        let file = dir.join("triggers").join("aria.json");
+
+        let triggers = Triggers::default();
+        let unbound = rule(json!({ "event": "death", "action": "look" }));
+        assert!(matches!(
+            triggers.add(unbound.clone()),
+            Err(TriggerError::NoCharacter)
+        ));
+
+        triggers.open(file.clone());
+        assert!(matches!(
+            triggers.add(rule(json!({ "action": "look" }))),
+            Err(TriggerError::Unconstrained)
+        ));
+        assert!(matches!(
+            triggers.add(rule(
+                json!({ "event": "combat_update", "action": "attack", "cooldown_secs": 0 })
+            )),
+            Err(TriggerError::CooldownTooShort)
+        ));
+        let added = triggers
+            .add(rule(
+                json!({ "event": "combat_update", "action": "flee", "cooldown_secs": 10 }),
+            ))
+            .expect("added");
+
+        let start = Instant::now();
+        assert_eq!(triggers.fire(&hit(50), start).len(), 1);
+        assert!(triggers
+            .fire(&hit(50), start + Duration::from_secs(5))
+            .is_empty());
+        assert_eq!(
+            triggers
+                .fire(&hit(50), start + Duration::from_secs(11))
+                .len(),
+            1
+        );
+
+        let reloaded = Triggers::default();
+        reloaded.open(file);
+        assert_eq!(reloaded.list(), vec![added.clone()]);
+        assert!(reloaded.remove(added.id));
+        assert!(!reloaded.remove(added.id));
+        assert_eq!(reloaded.add(unbound).expect("added").id, added.id + 1);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
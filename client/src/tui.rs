@@ -0,0 +1,422 @@
+//! `wyvern tui`: a full-screen terminal client.
+//!
+//! Logs in like `wyvern play` and splits the screen into the current
+//! room, a scrolling log of responses and push events, HP and mana bars
+//! refreshed from `status`, the party from `party_list`, and a command
+//! line that takes the same commands as the REPL.
+
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use futures_util::StreamExt;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Gauge, List, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use serde_json::{Map, Value};
+use tokio::sync::mpsc;
+
+use crate::connection::ConnectionHandle;
+use crate::play::{self, Input, PlayOptions, Session};
+use crate::protocol::{PushEvent, ResponseBody, RoomView};
+
+/// Lines kept in the event log.
+const MAX_LOG_LINES: usize = 1000;
+
+/// Lines moved by Page Up and Page Down.
+const SCROLL_STEP: usize = 10;
+
+/// A current/maximum pair such as hit points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Meter {
+    current: i64,
+    max: Option<i64>,
+}
+
+impl Meter {
+    /// Reads `key` and its `max_<key>` from a status payload.
+    fn read(data: &Map<String, Value>, key: &str) -> Option<Self> {
+        Some(Self {
+            current: data.get(key)?.as_i64()?,
+            max: data.get(&format!("max_{key}")).and_then(Value::as_i64),
+        })
+    }
+
+    fn percent(self) -> u16 {
+        match self.max {
+            Some(max) if max > 0 => {
+                u16::try_from(self.current.clamp(0, max) * 100 / max).unwrap_or(100)
+            }
+            _ => 100,
+        }
+    }
+
+    fn label(self) -> String {
+        match self.max {
+            Some(max) => format!("{}/{max}", self.current),
+            None => self.current.to_string(),
+        }
+    }
+}
+
+/// Everything on screen.
+#[derive(Debug, Default)]
+struct App {
+    room: Vec<String>,
+    log: Vec<String>,
+    /// Lines the log is scrolled up from the newest.
+    scroll: usize,
+    hp: Option<Meter>,
+    mana: Option<Meter>,
+    party: Vec<String>,
+    input: String,
+    quit: bool,
+}
+
+impl App {
+    fn push_log(&mut self, line: &str) {
+        self.log.extend(line.lines().map(str::to_owned));
+        let excess = self.log.len().saturating_sub(MAX_LOG_LINES);
+        self.log.drain(..excess);
+    }
+
+    /// Updates the panes from the response to `action`.
+    fn apply(&mut self, action: &str, body: ResponseBody) {
+        let result = match body {
+            ResponseBody::Success(result) => result,
+            ResponseBody::Error { error } => {
+                self.push_log(&format!("error: {}", error.message));
+                return;
+            }
+        };
+        let hp = Meter::read(&result, "hp");
+        if hp.is_some() {
+            self.hp = hp;
+            self.mana = Meter::read(&result, "mana").or(self.mana);
+        }
+        match action {
+            "status" => {}
+            "party_list" => self.party = party_members(&result),
+            _ => {
+                if let Some(room) = RoomView::from_result(&result) {
+                    self.room = room_lines(&room, &result);
+                }
+                if let Some(message) = result.get("message").and_then(Value::as_str) {
+                    self.push_log(message);
+                } else if RoomView::from_result(&result).is_none() {
+                    self.push_log(&Value::Object(result).to_string());
+                }
+            }
+        }
+    }
+
+    /// Logs a push event, taking any hit points it reports.
+    fn apply_event(&mut self, event: &PushEvent) {
+        if let Some(Value::Object(data)) = event.to_message().get("data") {
+            if let Some(hp) = Meter::read(data, "hp") {
+                self.hp = Some(hp);
+            }
+        }
+        self.push_log(&play::render_event(event));
+    }
+
+    /// Edits the command line; returns a line once Enter is pressed.
+    fn on_key(&mut self, key: KeyEvent) -> Option<String> {
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.quit = true;
+            }
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Enter => return Some(std::mem::take(&mut self.input)),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::PageUp => {
+                self.scroll = (self.scroll + SCROLL_STEP).min(self.log.len());
+            }
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(SCROLL_STEP),
+            KeyCode::Char(c) => self.input.push(c),
+            _ => {}
+        }
+        None
+    }
+
+    fn draw(&self, frame: &mut Frame<'_>) {
+        let [main, command] =
+            Layout::vertical([Constraint::Min(6), Constraint::Length(3)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Min(30), Constraint::Length(28)]).areas(main);
+        let [room, log] =
+            Layout::vertical([Constraint::Percentage(40), Constraint::Min(3)]).areas(left);
+        let [hp, mana, party] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(3),
+        ])
+        .areas(right);
+
+        frame.render_widget(
+            Paragraph::new(self.room.join("\n"))
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title("Room")),
+            room,
+        );
+        self.draw_log(frame, log);
+        draw_meter(frame, hp, "HP", self.hp, Color::Red);
+        draw_meter(frame, mana, "Mana", self.mana, Color::Blue);
+        frame.render_widget(
+            List::new(self.party.iter().map(String::as_str))
+                .block(Block::bordered().title("Party")),
+            party,
+        );
+        frame.render_widget(
+            Paragraph::new(format!("> {}", self.input)).block(Block::bordered().title("Command")),
+            command,
+        );
+        let cursor_x = command.x + 3 + u16::try_from(self.input.chars().count()).unwrap_or(0);
+        frame.set_cursor_position((
+            cursor_x.min(command.right().saturating_sub(2)),
+            command.y + 1,
+        ));
+    }
+
+    fn draw_log(&self, frame: &mut Frame<'_>, area: Rect) {
+        let height = usize::from(area.height.saturating_sub(2));
+        let end = self.log.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(height);
+        let title = if self.scroll > 0 {
+            format!("Events (scrolled back {})", self.scroll)
+        } else {
+            "Events".to_owned()
+        };
+        frame.render_widget(
+            Paragraph::new(self.log[start..end].join("\n")).block(Block::bordered().title(title)),
+            area,
+        );
+    }
+}
+
+fn draw_meter(frame: &mut Frame<'_>, area: Rect, title: &str, meter: Option<Meter>, color: Color) {
+    let gauge = Gauge::default()
+        .block(Block::bordered().title(title))
+        .gauge_style(Style::new().fg(color));
+    let gauge = match meter {
+        Some(meter) => gauge.percent(meter.percent()).label(meter.label()),
+        None => gauge.percent(0).label("?"),
+    };
+    frame.render_widget(gauge, area);
+}
+
+/// The room pane: name, description and exits.
+fn room_lines(room: &RoomView, result: &Map<String, Value>) -> Vec<String> {
+    let description = result
+        .get("room")
+        .and_then(|room| room.get("description"))
+        .or_else(|| result.get("description"))
+        .and_then(Value::as_str);
+    let exits: Vec<_> = room.exits.keys().map(String::as_str).collect();
+    let mut lines = vec![room.name.clone().unwrap_or_else(|| room.id.clone())];
+    lines.extend(description.map(str::to_owned));
+    lines.push(format!("Exits: {}", exits.join(", ")));
+    lines
+}
+
+/// Reads party members given as names or as objects with a name.
+fn party_members(result: &Map<String, Value>) -> Vec<String> {
+    let members = result
+        .get("members")
+        .or_else(|| result.get("party"))
+        .and_then(Value::as_array);
+    members
+        .into_iter()
+        .flatten()
+        .filter_map(|member| match member {
+            Value::String(name) => Some(name.clone()),
+            Value::Object(member) => ["name", "player", "username"]
+                .iter()
+                .find_map(|key| member.get(*key).and_then(Value::as_str))
+                .map(str::to_owned),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Sends commands in the background so the screen keeps updating while
+/// the server answers.
+#[derive(Debug, Clone)]
+struct Dispatcher {
+    handle: ConnectionHandle,
+    done: mpsc::UnboundedSender<(&'static str, ResponseBody)>,
+    errors: mpsc::UnboundedSender<String>,
+}
+
+impl Dispatcher {
+    fn send(&self, action: &'static str, params: Value) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            match this.handle.send_command(action, params).await {
+                Ok(response) => {
+                    let _ = this.done.send((action, response.body));
+                }
+                Err(err) => {
+                    let _ = this.errors.send(format!("error: {err}"));
+                }
+            }
+        });
+    }
+}
+
+/// Logs in and runs the full-screen client until the player quits.
+///
+/// # Errors
+///
+/// Returns an error if the server cannot be reached, the login is
+/// refused, or the terminal cannot be drawn.
+pub async fn run(options: PlayOptions) -> anyhow::Result<()> {
+    let Session {
+        mut connection,
+        events,
+        welcome,
+        new_account,
+    } = play::login(options).await?;
+
+    let mut app = App::default();
+    if new_account {
+        app.push_log(play::NEW_ACCOUNT_NOTE);
+    }
+    app.apply("connect", ResponseBody::Success(welcome));
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, connection.handle(), events).await;
+    ratatui::restore();
+    connection.disconnect().await;
+    result
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    handle: ConnectionHandle,
+    mut events: crate::events::EventBuffer,
+) -> anyhow::Result<()> {
+    let (done_tx, mut done_rx) = mpsc::unbounded_channel();
+    let (errors_tx, mut errors_rx) = mpsc::unbounded_channel();
+    let dispatcher = Dispatcher {
+        handle,
+        done: done_tx,
+        errors: errors_tx,
+    };
+    dispatcher.send("status", serde_json::json!({}));
+    dispatcher.send("party_list", serde_json::json!({}));
+
+    let mut keys = EventStream::new();
+    while !app.quit {
+        terminal.draw(|frame| app.draw(frame))?;
+        tokio::select! {
+            key = keys.next() => match key {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    if let Some(line) = app.on_key(key) {
+                        run_line(app, &dispatcher, &line);
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err.into()),
+                None => return Ok(()),
+            },
+            Some(event) = events.recv() => app.apply_event(&event),
+            Some((action, body)) = done_rx.recv() => {
+                app.apply(action, body);
+                // Keep the side panes current after anything the player does.
+                if action != "status" && action != "party_list" {
+                    dispatcher.send("status", serde_json::json!({}));
+                    if action.starts_with("party_") {
+                        dispatcher.send("party_list", serde_json::json!({}));
+                    }
+                }
+            }
+            Some(error) = errors_rx.recv() => app.push_log(&error),
+        }
+    }
+    Ok(())
+}
+
+/// Runs one line typed on the command line.
+fn run_line(app: &mut App, dispatcher: &Dispatcher, line: &str) {
+    match play::parse(line) {
+        Ok(Input::Send { action, params }) => {
+            app.push_log(&format!("> {line}"));
+            app.scroll = 0;
+            dispatcher.send(action, params);
+        }
+        Ok(Input::Help) => app.push_log(&play::help()),
+        Ok(Input::Quit) => app.quit = true,
+        Ok(Input::Empty) => {}
+        Err(message) => app.push_log(&message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use serde_json::json;
+
+    use super::*;
+
+    fn success(value: Value) -> ResponseBody {
+        match value {
+            Value::Object(result) => ResponseBody::Success(result),
+            other => panic!("not an object: {other}"),
+        }
+    }
+
+    #[test]
+    fn panes_show_room_status_party_and_events() {
+        let mut app = App::default();
+        app.apply(
+            "look",
+            success(json!({ "room": {
+                "name": "Town Square",
+                "description": "A busy square.",
+                "exits": { "north": "gate" }
+            } })),
+        );
+        app.apply(
+            "status",
+            success(json!({ "hp": 20, "max_hp": 20, "mana": 4, "max_mana": 10 })),
+        );
+        app.apply(
+            "party_list",
+            success(json!({ "members": ["Aria", { "name": "Brom" }] })),
+        );
+        app.apply_event(&PushEvent::from_message(json!({
+            "type": "event",
+            "data": { "event": "combat_update", "attacker": "goblin", "hp": 17, "max_hp": 20 }
+        })));
+        assert_eq!(
+            app.hp,
+            Some(Meter {
+                current: 17,
+                max: Some(20)
+            })
+        );
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).expect("terminal");
+        terminal.draw(|frame| app.draw(frame)).expect("drawn");
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(ratatui::buffer::Cell::symbol)
+            .collect();
+        for text in [
+            "Town Square",
+            "Exits: north",
+            "17/20",
+            "4/10",
+            "Brom",
+            "[combat_update]",
+        ] {
+            assert!(screen.contains(text), "`{text}` not on screen");
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! Client-side map of the rooms a character has explored.
+//!
+//! Every room seen in a `look` or `move` response is recorded together
+//! with its exits, and each successful move links the room it started
+//! from to the room it arrived in. The graph is saved per character at
+//! `<token_path>/maps/<username>.json`, so routes learned in one session
+//! are available in the next.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::protocol::{ResponseBody, RoomView};
+use crate::store;
+
+/// Errors from planning a route.
+#[derive(Debug, thiserror::Error)]
+pub enum RouteError {
+    /// The current room has not been seen yet.
+    #[error("current room is unknown; use `look` first")]
+    UnknownPosition,
+    /// No explored room matches the destination.
+    #[error("no explored room matches {0:?}")]
+    UnknownDestination(String),
+    /// The explored map does not connect the two rooms.
+    #[error("no known route from {from} to {to}; explore more of the area first")]
+    NoRoute {
+        /// Room the route would start from.
+        from: String,
+        /// Room the route would end in.
+        to: String,
+    },
+}
+
+/// One step of a planned route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// Exit to take.
+    pub direction: String,
+    /// Room the exit is known to lead to.
+    pub room: String,
+}
+
+/// A room in the explored graph.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct RoomNode {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// Exit direction mapped to the destination room, once known.
+    #[serde(default)]
+    exits: BTreeMap<String, Option<String>>,
+}
+
+/// Graph of explored rooms and the exits between them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorldMap {
+    rooms: BTreeMap<String, RoomNode>,
+}
+
+impl WorldMap {
+    /// Records a room and its exits, returning true if the map changed.
+    ///
+    /// Destinations learned by walking an exit are kept when the server
+    /// lists that exit without one.
+    pub fn observe(&mut self, room: &RoomView) -> bool {
+        let node = self.rooms.entry(room.id.clone()).or_default();
+        let mut next = node.clone();
+        if room.name.is_some() {
+            next.name.clone_from(&room.name);
+        }
+        let mut exits = BTreeMap::new();
+        for (direction, to) in &room.exits {
+            let known = node.exits.get(direction).cloned().flatten();
+            exits.insert(direction.clone(), to.clone().or(known));
+        }
+        next.exits = exits;
+
+        let changed = *node != next;
+        *node = next;
+        changed
+    }
+
+    /// Records that taking `direction` from `from` leads to `to`.
+    pub fn link(&mut self, from: &str, direction: &str, to: &str) -> bool {
+        let exit = self
+            .rooms
+            .entry(from.to_owned())
+            .or_default()
+            .exits
+            .entry(direction.to_owned())
+            .or_default();
+        if exit.as_deref() == Some(to) {
+            return false;
+        }
+        *exit = Some(to.to_owned());
+        true
+    }
+
+    /// Finds the room matching `query` by ID, or else by unique name.
+    pub fn resolve(&self, query: &str) -> Option<&str> {
+        if let Some((id, _)) = self.rooms.get_key_value(query) {
+            return Some(id);
+        }
+        let mut named = self.rooms.iter().filter(|(_, node)| {
+            node.name
+                .as_deref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(query))
+        });
+        match (named.next(), named.next()) {
+            (Some((id, _)), None) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Finds a shortest route between two rooms over known exits.
+    pub fn route(&self, from: &str, to: &str) -> Option<Vec<Step>> {
+        let mut came_from: HashMap<&str, (&str, &str)> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(room) = queue.pop_front() {
+            if room == to {
+                let mut steps = Vec::new();
+                let mut at = to;
+                while at != from {
+                    let (prev, direction) = came_from[at];
+                    steps.push(Step {
+                        direction: direction.to_owned(),
+                        room: at.to_owned(),
+                    });
+                    at = prev;
+                }
+                steps.reverse();
+                return Some(steps);
+            }
+            let Some(node) = self.rooms.get(room) else {
+                continue;
+            };
+            for (direction, next) in &node.exits {
+                let Some(next) = next.as_deref() else {
+                    continue;
+                };
+                if next != from && !came_from.contains_key(next) {
+                    came_from.insert(next, (room, direction));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+struct TrackerInner {
+    map: WorldMap,
+    file: Option<PathBuf>,
+    current: Option<String>,
+}
+
+/// Keeps the active character's map up to date from server responses.
+#[derive(Debug, Clone, Default)]
+pub struct WorldTracker {
+    inner: Arc<Mutex<TrackerInner>>,
+}
+
+impl WorldTracker {
+    /// Switches to the map stored at `file`, loading it if it exists.
+    pub fn open(&self, file: PathBuf) {
+        let map = store::load(&file);
+        *self.lock() = TrackerInner {
+            map,
+            file: Some(file),
+            current: None,
+        };
+    }
+
+    /// Forgets the active map, e.g. on disconnect.
+    pub fn close(&self) {
+        *self.lock() = TrackerInner::default();
+    }
+
+    /// Returns the ID of the room the character was last seen in.
+    pub fn current(&self) -> Option<String> {
+        self.lock().current.clone()
+    }
+
+    /// Updates the map from the response to `action`.
+    pub fn record(&self, action: &str, params: &Value, body: &ResponseBody) {
+        let moved = matches!(action, "move" | "flee");
+        let describes_room = match action {
+            "connect" | "move" | "flee" => true,
+            // `look <target>` examines something instead of describing the room.
+            "look" => params.get("target").is_none(),
+            _ => false,
+        };
+        let ResponseBody::Success(result) = body else {
+            return;
+        };
+        if !describes_room {
+            return;
+        }
+
+        let mut inner = self.lock();
+        let Some(room) = RoomView::from_result(result) else {
+            if moved {
+                // We went somewhere, but not anywhere we can place on the map.
+                inner.current = None;
+            }
+            return;
+        };
+
+        let mut changed = inner.map.observe(&room);
+        if action == "move" {
+            let direction = params.get("direction").and_then(Value::as_str);
+            if let (Some(from), Some(direction)) = (inner.current.clone(), direction) {
+                changed |= inner.map.link(&from, direction, &room.id);
+            }
+        }
+        inner.current = Some(room.id);
+
+        if changed {
+            if let Some(file) = &inner.file {
+                store::save(file, &inner.map);
+            }
+        }
+    }
+
+    /// Plans a shortest route from the current room to `destination`,
+    /// given as a room ID or name.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RouteError`] if the current room or destination is
+    /// unknown, or no explored path connects them.
+    pub fn route_to(&self, destination: &str) -> Result<Vec<Step>, RouteError> {
+        let inner = self.lock();
+        let from = inner
+            .current
+            .as_deref()
+            .ok_or(RouteError::UnknownPosition)?;
+        let to = inner
+            .map
+            .resolve(destination)
+            .ok_or_else(|| RouteError::UnknownDestination(destination.to_owned()))?;
+        inner
+            .map
+            .route(from, to)
+            .ok_or_else(|| RouteError::NoRoute {
+                from: from.to_owned(),
+                to: to.to_owned(),
+            })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TrackerInner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn room(value: Value) -> ResponseBody {
+        serde_json::from_value(value).expect("valid response body")
+    }
+
+    /// Walks square -> north -> gate -> east -> tower, learning both links.
+    fn explore(tracker: &WorldTracker) {
+        tracker.record(
+            "look",
+            &json!({}),
+            &room(json!({ "room": { "id": "square", "name": "Town Square", "exits": ["north"] } })),
+        );
+        tracker.record(
+            "move",
+            &json!({ "direction": "north" }),
+            &room(json!({ "room": { "id": "gate", "name": "North Gate", "exits": ["south", "east"] } })),
+        );
+        tracker.record(
+            "move",
+            &json!({ "direction": "east" }),
+            &room(json!({ "room": { "id": "tower", "name": "Wizard Tower", "exits": ["west"] } })),
+        );
+    }
+
+    #[test]
+    fn moves_build_a_routable_graph() {
+        let tracker = WorldTracker::default();
+        explore(&tracker);
+        assert_eq!(tracker.current().as_deref(), Some("tower"));
+
+        // Walk back so the route starts at the square.
+        tracker.record(
+            "move",
+            &json!({ "direction": "west" }),
+            &room(json!({ "room": { "id": "gate", "exits": ["south", "east"] } })),
+        );
+        tracker.record(
+            "move",
+            &json!({ "direction": "south" }),
+            &room(json!({ "room": { "id": "square", "exits": ["north"] } })),
+        );
+
+        let route = tracker.route_to("wizard tower").expect("route");
+        let directions: Vec<_> = route.iter().map(|step| step.direction.as_str()).collect();
+        assert_eq!(directions, ["north", "east"]);
+        assert_eq!(route.last().map(|step| step.room.as_str()), Some("tower"));
+
+        assert!(matches!(
+            tracker.route_to("Dragon Lair"),
+            Err(RouteError::UnknownDestination(_))
+        ));
+    }
+
+    #[test]
+    fn one_way_knowledge_has_no_route_back() {
+        let tracker = WorldTracker::default();
+        explore(&tracker);
+        // The tower's west exit has never been walked.
+        assert!(matches!(
+            tracker.route_to("square"),
+            Err(RouteError::NoRoute { .. })
+        ));
+    }
+
+    #[test]
+    fn map_is_saved_and_reloaded_per_character() {
+        let dir = std::env::temp_dir().join(format!("wyvern-world-{}", std::process::id()));
+        let file = dir.join("maps").join("aria.json");
+
+        let tracker = WorldTracker::default();
+        tracker.open(file.clone());
+        explore(&tracker);
+        tracker.close();
+
+        let reloaded = WorldTracker::default();
+        reloaded.open(file);
+        assert_eq!(reloaded.current(), None);
+        reloaded.record(
+            "look",
+            &json!({}),
+            &room(json!({ "room": { "id": "square", "exits": ["north"] } })),
+        );
+        assert_eq!(reloaded.route_to("tower").expect("route").len(), 2);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}